@@ -1,5 +1,8 @@
 use std::env;
+use std::fs;
+use std::path::Path;
 use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 fn main() {
     // This is ran only during build process, we expect to always have git when building the app
@@ -13,5 +16,35 @@ fn main() {
         env!("CARGO_PKG_VERSION"),
         git_hash
     );
+    println!("cargo:rustc-env=PERIDIO_CLI_GIT_SHA={}", git_hash.trim());
     println!("cargo:rustc-env=TARGET={}", env::var("TARGET").unwrap());
+
+    // honour SOURCE_DATE_EPOCH so reproducible builds stay reproducible
+    let build_timestamp = env::var("SOURCE_DATE_EPOCH").unwrap_or_else(|_| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string()
+    });
+    println!("cargo:rustc-env=PERIDIO_CLI_BUILD_TIMESTAMP={build_timestamp}");
+
+    let lock_file = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!(
+        "cargo:rustc-env=PERIDIO_SDK_VERSION={}",
+        locked_version(&lock_file, "peridio-sdk").unwrap_or_default()
+    );
+}
+
+/// Finds the version of `package` pinned in a Cargo.lock.
+fn locked_version(lock_file: &Path, package: &str) -> Option<String> {
+    let lock = fs::read_to_string(lock_file).ok()?;
+    let name = format!("name = \"{package}\"");
+    let mut lines = lock.lines().skip_while(|line| *line != name).skip(1);
+
+    lines
+        .next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+        .map(str::to_owned)
 }
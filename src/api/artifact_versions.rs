@@ -1,12 +1,14 @@
 use std::fs;
 
 use super::Command;
+use crate::api::get::GetArgs;
 use crate::api::list::ListArgs;
 use crate::print_json;
 use crate::utils::maybe_json;
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use crate::NonExistingPathSnafu;
@@ -100,7 +102,10 @@ impl Command<CreateCommand> {
             .context(ApiSnafu)?
         {
             Some(artifact_version) => print_json!(&artifact_version),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "artifact-versions create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -115,30 +120,27 @@ pub struct ListCommand {
 
 impl Command<ListCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let params = ListArtifactVersionsParams {
-            limit: self.inner.list_args.limit,
-            order: self.inner.list_args.order,
-            search: self.inner.list_args.search,
-            page: self.inner.list_args.page,
-        };
-
         let api = Api::new(ApiOptions {
             api_key: global_options.api_key.unwrap(),
             endpoint: global_options.base_url,
             ca_bundle_path: global_options.ca_path,
         });
 
-        match api
-            .artifact_versions()
-            .list(params)
-            .await
-            .context(ApiSnafu)?
-        {
-            Some(artifact_version) => print_json!(&artifact_version),
-            None => panic!(),
-        }
+        let list_args = &self.inner.list_args;
 
-        Ok(())
+        list_args
+            .print_pages("artifact-versions list", |page| {
+                let params = ListArtifactVersionsParams {
+                    limit: list_args.limit,
+                    order: list_args.order.clone(),
+                    search: list_args.search_query(&[]),
+                    page,
+                };
+                let api = api.clone();
+
+                async move { api.artifact_versions().list(params).await }
+            })
+            .await
     }
 }
 
@@ -150,6 +152,9 @@ pub struct GetCommand {
         value_parser = PRNValueParser::new(PRNType::ArtifactVersion)
     )]
     prn: String,
+
+    #[clap(flatten)]
+    get_args: GetArgs,
 }
 
 impl Command<GetCommand> {
@@ -170,8 +175,11 @@ impl Command<GetCommand> {
             .await
             .context(ApiSnafu)?
         {
-            Some(artifact_version) => print_json!(&artifact_version),
-            None => panic!(),
+            Some(artifact_version) => self.inner.get_args.print(&artifact_version)?,
+            None => EmptyResponseSnafu {
+                context: "artifact-versions get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -188,9 +196,13 @@ pub struct UpdateCommand {
     prn: String,
 
     /// A JSON object that informs the metadata that will be associated with this artifact version's binaries when they are included in bundles.
-    #[arg(long)]
+    #[arg(long, conflicts_with = "custom_metadata_path")]
     pub custom_metadata: Option<String>,
 
+    /// The path to the JSON file value for custom_metadata
+    #[arg(long, conflicts_with = "custom_metadata")]
+    pub custom_metadata_path: Option<String>,
+
     /// An arbitrary string attached to the resource. Often useful for displaying to users.
     #[arg(long)]
     pub description: Option<String>,
@@ -198,9 +210,19 @@ pub struct UpdateCommand {
 
 impl Command<UpdateCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let custom_metadata = if let Some(custom_metadata_path) = self.inner.custom_metadata_path {
+            fs::read_to_string(&custom_metadata_path)
+                .context(NonExistingPathSnafu {
+                    path: &custom_metadata_path,
+                })?
+                .into()
+        } else {
+            self.inner.custom_metadata
+        };
+
         let params = UpdateArtifactVersionParams {
             prn: self.inner.prn,
-            custom_metadata: maybe_json(self.inner.custom_metadata),
+            custom_metadata: maybe_json(custom_metadata),
             description: self.inner.description,
         };
 
@@ -217,7 +239,10 @@ impl Command<UpdateCommand> {
             .context(ApiSnafu)?
         {
             Some(artifact_version) => print_json!(&artifact_version),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "artifact-versions update",
+            }
+            .fail()?,
         }
 
         Ok(())
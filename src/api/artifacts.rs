@@ -1,12 +1,14 @@
 use std::fs;
 
 use super::Command;
+use crate::api::get::GetArgs;
 use crate::api::list::ListArgs;
 use crate::print_json;
 use crate::utils::maybe_json;
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use crate::NonExistingPathSnafu;
@@ -96,7 +98,10 @@ impl Command<CreateCommand> {
 
         match api.artifacts().create(params).await.context(ApiSnafu)? {
             Some(artifact) => print_json!(&artifact),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "artifacts create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -111,25 +116,27 @@ pub struct ListCommand {
 
 impl Command<ListCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let params = ListArtifactsParams {
-            limit: self.inner.list_args.limit,
-            order: self.inner.list_args.order,
-            search: self.inner.list_args.search,
-            page: self.inner.list_args.page,
-        };
-
         let api = Api::new(ApiOptions {
             api_key: global_options.api_key.unwrap(),
             endpoint: global_options.base_url,
             ca_bundle_path: global_options.ca_path,
         });
 
-        match api.artifacts().list(params).await.context(ApiSnafu)? {
-            Some(artifact) => print_json!(&artifact),
-            None => panic!(),
-        }
-
-        Ok(())
+        let list_args = &self.inner.list_args;
+
+        list_args
+            .print_pages("artifacts list", |page| {
+                let params = ListArtifactsParams {
+                    limit: list_args.limit,
+                    order: list_args.order.clone(),
+                    search: list_args.search_query(&[]),
+                    page,
+                };
+                let api = api.clone();
+
+                async move { api.artifacts().list(params).await }
+            })
+            .await
     }
 }
 
@@ -141,6 +148,9 @@ pub struct GetCommand {
         value_parser = PRNValueParser::new(PRNType::Artifact)
     )]
     prn: String,
+
+    #[clap(flatten)]
+    get_args: GetArgs,
 }
 
 impl Command<GetCommand> {
@@ -156,8 +166,11 @@ impl Command<GetCommand> {
         });
 
         match api.artifacts().get(params).await.context(ApiSnafu)? {
-            Some(artifact) => print_json!(&artifact),
-            None => panic!(),
+            Some(artifact) => self.inner.get_args.print(&artifact)?,
+            None => EmptyResponseSnafu {
+                context: "artifacts get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -174,9 +187,13 @@ pub struct UpdateCommand {
     prn: String,
 
     /// A JSON object that informs the metadata that will be associated with this artifact's binaries when they are included in bundles.
-    #[arg(long)]
+    #[arg(long, conflicts_with = "custom_metadata_path")]
     pub custom_metadata: Option<String>,
 
+    /// The path to the JSON file value for custom_metadata
+    #[arg(long, conflicts_with = "custom_metadata")]
+    pub custom_metadata_path: Option<String>,
+
     /// An arbitrary string attached to the resource. Often useful for displaying to users.
     #[arg(long)]
     pub description: Option<String>,
@@ -188,9 +205,19 @@ pub struct UpdateCommand {
 
 impl Command<UpdateCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let custom_metadata = if let Some(custom_metadata_path) = self.inner.custom_metadata_path {
+            fs::read_to_string(&custom_metadata_path)
+                .context(NonExistingPathSnafu {
+                    path: &custom_metadata_path,
+                })?
+                .into()
+        } else {
+            self.inner.custom_metadata
+        };
+
         let params = UpdateArtifactParams {
             prn: self.inner.prn,
-            custom_metadata: maybe_json(self.inner.custom_metadata),
+            custom_metadata: maybe_json(custom_metadata),
             description: self.inner.description,
             name: self.inner.name,
         };
@@ -203,7 +230,10 @@ impl Command<UpdateCommand> {
 
         match api.artifacts().update(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "artifacts update",
+            }
+            .fail()?,
         }
 
         Ok(())
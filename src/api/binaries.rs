@@ -1,21 +1,26 @@
 use super::Command;
+use crate::api::binary_parts::is_uploaded;
+use crate::api::get::GetArgs;
 use crate::api::list::ListArgs;
 use crate::print_json;
+use crate::print_status;
 use crate::utils::maybe_json;
+use crate::utils::retry::{is_rate_limited, is_transient, retry_with_backoff, DEFAULT_BASE_DELAY};
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
+use crate::FileSnafu;
 use crate::GlobalOptions;
 use crate::NonExistingPathSnafu;
-use backon::ConstantBuilder;
-use backon::Retryable;
 use base64::engine::general_purpose;
 use base64::Engine;
 use clap::Parser;
 use futures_util::stream;
 use futures_util::StreamExt;
 use indicatif::ProgressBar;
+use indicatif::ProgressDrawTarget;
 use indicatif::ProgressState;
 use indicatif::ProgressStyle;
 use peridio_sdk::api::binaries::Binary;
@@ -29,20 +34,25 @@ use peridio_sdk::api::binaries::ListBinariesResponse;
 use peridio_sdk::api::binaries::UpdateBinaryParams;
 use peridio_sdk::api::binaries::UpdateBinaryResponse;
 use peridio_sdk::api::binary_parts::BinaryPartState;
+use peridio_sdk::api::binary_parts::CreateBinaryPartParams;
 use peridio_sdk::api::binary_parts::ListBinaryPart;
 use peridio_sdk::api::Api;
 use peridio_sdk::api::ApiOptions;
 use reqwest::Body;
 use reqwest::Client;
+use reqwest::StatusCode;
 use sha2::{Digest, Sha256};
 use snafu::ResultExt;
 use std::cmp;
+use std::fmt;
+use std::io::IsTerminal;
 use std::io::Read;
 use std::io::Seek;
 use std::sync::Arc;
 use std::thread::available_parallelism;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{fs, io};
+use tempfile::NamedTempFile;
 
 #[derive(Parser, Debug)]
 pub enum BinariesCommand {
@@ -50,6 +60,7 @@ pub enum BinariesCommand {
     List(Command<ListCommand>),
     Get(Command<GetCommand>),
     Update(Command<UpdateCommand>),
+    Verify(Command<VerifyCommand>),
 }
 
 impl BinariesCommand {
@@ -59,6 +70,7 @@ impl BinariesCommand {
             Self::List(cmd) => cmd.run(global_options).await,
             Self::Get(cmd) => cmd.run(global_options).await,
             Self::Update(cmd) => cmd.run(global_options).await,
+            Self::Verify(cmd) => cmd.run(global_options).await,
         }
     }
 }
@@ -111,7 +123,9 @@ pub struct CreateCommand {
     #[arg(long)]
     target: String,
 
-    /// The path to the file you wish to upload as the binary's content.
+    /// The path to the file you wish to upload as the binary's content, or `-` to read it from stdin.
+    ///
+    /// Content read from stdin is buffered to a temporary file before hashing and uploading, so the temporary directory (TMPDIR on Unix) needs room for all of it. The file is removed afterwards.
     #[arg(
         long,
         conflicts_with_all = ["hash", "size"],
@@ -128,8 +142,16 @@ pub struct CreateCommand {
     )]
     binary_part_size: Option<u64>,
 
-    /// Limit the concurrency of jobs that create and upload binary parts. [default: 2x the core count, to a maximum of 16]
-    #[arg(long, requires = "content_path")]
+    /// Choose the binary part size from the size of the content instead, so that it is split into at most 1000 parts.
+    #[arg(long, requires = "content_path", conflicts_with = "binary_part_size")]
+    auto_part_size: bool,
+
+    /// Limit the concurrency of jobs that create and upload binary parts. [default: 2x the core count, to a maximum of 16, or 4 if the core count cannot be determined]
+    #[arg(
+        long,
+        requires = "content_path",
+        value_parser = clap::value_parser!(u8).range(1..),
+    )]
     concurrency: Option<u8>,
 
     /// The name of a signing key pair in your Peridio CLI config. This will dictate both the private key to create a binary signature with as well as the signing key Peridio will use to verify the binary signature.
@@ -161,6 +183,22 @@ pub struct CreateCommand {
     )]
     signing_key_prn: Option<String>,
 
+    /// The number of times to retry an API request that was rate limited, or a binary part upload that failed transiently, backing off exponentially between attempts.
+    #[arg(long, default_value_t = 5)]
+    max_retries: u8,
+
+    /// The maximum number of seconds to wait for cloud hashing to make the binary signable.
+    #[arg(long, default_value_t = 300)]
+    signable_timeout: u64,
+
+    /// The number of seconds between checks of whether the binary has become signable.
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    signable_poll_interval: u64,
+
+    /// Do not display a progress bar while uploading. The progress bar is also hidden when stderr is not a terminal.
+    #[arg(long, requires = "content_path")]
+    no_progress: bool,
+
     /// Create the binary record but do not upload its content nor sign it.
     #[arg(
         long,
@@ -188,6 +226,18 @@ impl CreateCommand {
 
         self.global_options = Some(global_options.clone());
 
+        // a pipe can only be read once, but the content is hashed before it is uploaded, so it
+        // is buffered to a temporary file that is removed when this guard is dropped
+        let _stdin_content = if self.content_path.as_deref() == Some("-") {
+            print_status!("Reading binary content from stdin...");
+            let mut file = NamedTempFile::new().context(FileSnafu)?;
+            io::copy(&mut io::stdin().lock(), &mut file).context(FileSnafu)?;
+            self.content_path = Some(file.path().to_string_lossy().into_owned());
+            Some(file)
+        } else {
+            None
+        };
+
         let binary = match self.get_or_create_binary(&api).await? {
             Some(CreateBinaryResponse { binary }) => {
                 if self.skip_upload {
@@ -195,14 +245,33 @@ impl CreateCommand {
                 }
 
                 if self.concurrency.is_none() {
-                    // default to 2x the core count
+                    // default to 2x the core count, falling back to 4 where it can't be detected
                     self.concurrency = Some(
-                        cmp::min(available_parallelism().unwrap().get() * 2, 16)
+                        available_parallelism()
+                            .map(|cores| cmp::min(cores.get() * 2, 16))
+                            .unwrap_or(4)
                             .try_into()
                             .unwrap(),
                     );
                 }
 
+                if self.auto_part_size {
+                    let content_path = self.content_path.clone().unwrap();
+                    let file_size = fs::metadata(&content_path)
+                        .context(NonExistingPathSnafu {
+                            path: &content_path,
+                        })?
+                        .len();
+                    let binary_part_size = auto_binary_part_size(file_size);
+
+                    print_status!(
+                        "Using a binary part size of {binary_part_size} bytes ({} parts)",
+                        file_size.div_ceil(binary_part_size)
+                    );
+
+                    self.binary_part_size = Some(binary_part_size);
+                }
+
                 let binary = self.process_binary(&binary, &api).await?;
 
                 Some(CreateBinaryResponse { binary })
@@ -215,29 +284,23 @@ impl CreateCommand {
 
     async fn process_binary(&self, binary: &Binary, api: &Api) -> Result<Binary, Error> {
         if matches!(binary.state, BinaryState::Uploadable) {
-            let binary = self.process_binary_parts(binary, api).await.unwrap();
+            let binary = self.process_binary_parts(binary, api).await?;
 
             // do signing if available
             if self.signing_key_pair.is_some() || self.signing_key_private.is_some() {
                 // wait for hashing to be signable
-                eprintln!("Waiting for cloud hashing...");
-                let binary = (|| async { self.check_for_state_change(&binary, api).await })
-                    .retry(
-                        &ConstantBuilder::default()
-                            .with_delay(Duration::new(10, 0))
-                            .with_max_times(30),
-                    )
-                    .await?;
+                print_status!("Waiting for cloud hashing...");
+                let binary = self.wait_for_signable(&binary, api).await?;
 
-                eprintln!("Signing binary...");
-                let binary = self.sign_binary(&binary, api).await.unwrap();
+                print_status!("Signing binary...");
+                let binary = self.sign_binary(&binary, api).await?;
 
                 Ok(binary)
             } else {
                 Ok(binary)
             }
         } else if matches!(binary.state, BinaryState::Hashable) {
-            eprintln!("Updating binary to hashing...");
+            print_status!("Updating binary to hashing...");
             // move to hashing
             let binary = self
                 .change_binary_status(ArgBinaryState::Hashing, binary, api)
@@ -246,17 +309,11 @@ impl CreateCommand {
 
             if self.signing_key_pair.is_some() || self.signing_key_private.is_some() {
                 // wait for hashing to be signable
-                eprintln!("Waiting for cloud hashing...");
-                let binary = (|| async { self.check_for_state_change(&binary, api).await })
-                    .retry(
-                        &ConstantBuilder::default()
-                            .with_delay(Duration::new(10, 0))
-                            .with_max_times(30),
-                    )
-                    .await?;
+                print_status!("Waiting for cloud hashing...");
+                let binary = self.wait_for_signable(&binary, api).await?;
 
-                eprintln!("Signing binary...");
-                let binary = self.sign_binary(&binary, api).await.unwrap();
+                print_status!("Signing binary...");
+                let binary = self.sign_binary(&binary, api).await?;
 
                 Ok(binary)
             } else {
@@ -265,17 +322,11 @@ impl CreateCommand {
         } else if matches!(binary.state, BinaryState::Hashing) {
             if self.signing_key_pair.is_some() || self.signing_key_private.is_some() {
                 // wait for hashing to be signable
-                eprintln!("Waiting for cloud hashing...");
-                let binary = (|| async { self.check_for_state_change(binary, api).await })
-                    .retry(
-                        &ConstantBuilder::default()
-                            .with_delay(Duration::new(10, 0))
-                            .with_max_times(30),
-                    )
-                    .await?;
+                print_status!("Waiting for cloud hashing...");
+                let binary = self.wait_for_signable(binary, api).await?;
 
-                eprintln!("Signing binary...");
-                let binary = self.sign_binary(&binary, api).await.unwrap();
+                print_status!("Signing binary...");
+                let binary = self.sign_binary(&binary, api).await?;
 
                 Ok(binary)
             } else {
@@ -283,8 +334,8 @@ impl CreateCommand {
             }
         } else if matches!(binary.state, BinaryState::Signable) {
             if self.signing_key_pair.is_some() || self.signing_key_private.is_some() {
-                eprintln!("Signing binary...");
-                let binary = self.sign_binary(binary, api).await.unwrap();
+                print_status!("Signing binary...");
+                let binary = self.sign_binary(binary, api).await?;
 
                 Ok(binary)
             } else {
@@ -296,18 +347,28 @@ impl CreateCommand {
     }
 
     async fn sign_binary(&self, binary: &Binary, api: &Api) -> Result<Binary, Error> {
-        let command = crate::api::binary_signatures::CreateCommand {
-            binary_prn: binary.prn.clone(),
-            binary_content_path: Some(self.content_path.clone().unwrap()),
-            signature: None,
-            signing_key_pair: self.signing_key_pair.clone(),
-            signing_key_private: self.signing_key_private.clone(),
-            signing_key_prn: self.signing_key_prn.clone(),
-            api: Some(api.clone()),
-            binary_content_hash: binary.hash.clone(),
-        };
+        let response = retry_with_backoff(
+            || async {
+                let command = crate::api::binary_signatures::CreateCommand {
+                    binary_prn: binary.prn.clone(),
+                    binary_content_path: Some(self.content_path.clone().unwrap()),
+                    signature: None,
+                    signing_key_pair: self.signing_key_pair.clone(),
+                    signing_key_private: self.signing_key_private.clone(),
+                    signing_key_prn: self.signing_key_prn.clone(),
+                    api: Some(api.clone()),
+                    binary_content_hash: binary.hash.clone(),
+                };
+
+                command.run(self.global_options.clone().unwrap()).await
+            },
+            self.max_retries.into(),
+            DEFAULT_BASE_DELAY,
+            is_rate_limited,
+        )
+        .await?;
 
-        match command.run(self.global_options.clone().unwrap()).await? {
+        match response {
             Some(_) => {
                 let binary = self
                     .change_binary_status(ArgBinaryState::Signed, binary, api)
@@ -316,29 +377,59 @@ impl CreateCommand {
 
                 Ok(binary)
             }
-            None => panic!("Failed signing binary"),
+            None => EmptyResponseSnafu {
+                context: "binary-signatures create",
+            }
+            .fail(),
         }
     }
 
-    async fn check_for_state_change(&self, binary: &Binary, api: &Api) -> Result<Binary, Error> {
-        let command = GetCommand {
-            prn: binary.prn.clone(),
-            api: Some(api.to_owned()),
-        };
-
-        match command.run(self.global_options.clone().unwrap()).await? {
-            Some(GetBinaryResponse { binary }) => {
-                if matches!(binary.state, BinaryState::Signable) {
-                    Ok(binary)
-                } else {
-                    Err(Error::Api {
-                        source: peridio_sdk::api::Error::Unknown {
-                            error: "failed at checking binary state changes".to_string(),
-                        },
-                    })
+    /// Polls the binary until cloud hashing has made it signable, giving up after `--signable-timeout`.
+    async fn wait_for_signable(&self, binary: &Binary, api: &Api) -> Result<Binary, Error> {
+        let timeout = Duration::from_secs(self.signable_timeout);
+        let poll_interval = Duration::from_secs(self.signable_poll_interval);
+        let started = Instant::now();
+        let mut last_logged = started;
+
+        loop {
+            let command = GetCommand {
+                prn: binary.prn.clone(),
+                get_args: GetArgs::default(),
+                api: Some(api.to_owned()),
+            };
+
+            let binary = match command.run(self.global_options.clone().unwrap()).await? {
+                Some(GetBinaryResponse { binary }) => binary,
+                None => EmptyResponseSnafu {
+                    context: "binaries get",
                 }
+                .fail()?,
+            };
+
+            if matches!(binary.state, BinaryState::Signable) {
+                return Ok(binary);
             }
-            None => panic!("Cannot get binary to check for state change"),
+
+            let elapsed = started.elapsed();
+
+            if elapsed >= timeout {
+                return Err(Error::SignableTimeout {
+                    prn: binary.prn,
+                    waited: elapsed.as_secs(),
+                    state: format!("{:?}", binary.state),
+                });
+            }
+
+            if last_logged.elapsed() >= Duration::from_secs(60) {
+                print_status!(
+                    "Still waiting for cloud hashing, {}s elapsed (state: {:?})...",
+                    elapsed.as_secs(),
+                    binary.state
+                );
+                last_logged = Instant::now();
+            }
+
+            tokio::time::sleep(cmp::min(poll_interval, timeout - elapsed)).await;
         }
     }
 
@@ -348,26 +439,39 @@ impl CreateCommand {
         binary: &Binary,
         api: &Api,
     ) -> Result<Binary, Error> {
-        let command = UpdateCommand {
-            prn: binary.prn.clone(),
-            custom_metadata: None,
-            description: None,
-            state: Some(state),
-            api: Some(api.clone()),
-            hash: None,
-            size: None,
-        };
+        let response = retry_with_backoff(
+            || async {
+                let command = UpdateCommand {
+                    prn: binary.prn.clone(),
+                    custom_metadata: None,
+                    description: None,
+                    state: Some(state.clone()),
+                    api: Some(api.clone()),
+                    hash: None,
+                    size: None,
+                };
 
-        match command.run(self.global_options.clone().unwrap()).await? {
+                command.run(self.global_options.clone().unwrap()).await
+            },
+            self.max_retries.into(),
+            DEFAULT_BASE_DELAY,
+            is_rate_limited,
+        )
+        .await?;
+
+        match response {
             Some(UpdateBinaryResponse { binary }) => Ok(binary),
-            None => panic!("Cannot update binary state"),
+            None => EmptyResponseSnafu {
+                context: "binaries update",
+            }
+            .fail(),
         }
     }
 
     async fn process_binary_parts(&self, binary: &Binary, api: &Api) -> Result<Binary, Error> {
-        eprintln!("Evaluating binary parts...");
+        print_status!("Evaluating binary parts...");
         // get server parts
-        let binary_parts = self.get_binary_parts(binary, api).await.unwrap();
+        let binary_parts = self.get_binary_parts(binary, api).await?;
 
         let file_size = {
             let file = fs::File::open(self.content_path.clone().unwrap()).context(
@@ -376,7 +480,7 @@ impl CreateCommand {
                 },
             )?;
 
-            file.metadata().unwrap().len()
+            file.metadata().context(FileSnafu)?.len()
         };
 
         let chunks_length =
@@ -394,9 +498,9 @@ impl CreateCommand {
         )
         .await?;
 
-        eprintln!("Validating Upload");
+        print_status!("Validating Upload");
         // list binary parts again in order to get the latest state
-        let binary_parts = self.get_binary_parts(binary, api).await.unwrap();
+        let binary_parts = self.get_binary_parts(binary, api).await?;
 
         // if the parts are not equal it means we missed a part
         // if a binary part state is not valid is because something is missing
@@ -406,7 +510,7 @@ impl CreateCommand {
                 .all(|x| matches!(x.state, BinaryPartState::Valid)))
         {
             // retry only once
-            eprintln!("Retrying Upload");
+            print_status!("Retrying Upload");
             self.upload_binary_parts(
                 binary,
                 api,
@@ -418,13 +522,13 @@ impl CreateCommand {
             .await?;
         }
 
-        eprintln!("Updating binary to hashable...");
+        print_status!("Updating binary to hashable...");
         // we created the binary parts not move it to hashable
         let binary = self
             .change_binary_status(ArgBinaryState::Hashable, binary, api)
             .await?;
 
-        eprintln!("Updating binary to hashing...",);
+        print_status!("Updating binary to hashing...");
         // move to hashing
         let binary = self
             .change_binary_status(ArgBinaryState::Hashing, &binary, api)
@@ -443,101 +547,150 @@ impl CreateCommand {
         client: &Client,
         binary_parts: &[ListBinaryPart],
     ) -> Result<(), Error> {
-        eprintln!("Creating binary parts and uploading...");
-        let pb = Arc::new(ProgressBar::new(file_size));
-        pb.set_style(ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-            .unwrap()
-            .with_key("eta", |state: &ProgressState, w: &mut dyn std::fmt::Write| write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap())
-            .progress_chars("#>-"));
-
-        let result = stream::iter(1..=chunks_length)
+        print_status!("Creating binary parts and uploading...");
+        let pb = Arc::new(progress_bar(file_size, self.no_progress));
+
+        let results = stream::iter(1..=chunks_length)
             .map(|index| {
                 let client = client.clone();
                 let binary_part_size = self.binary_part_size.unwrap();
-                let global_options = self.global_options.clone().unwrap();
+                let max_retries = self.max_retries.into();
                 let api = api.clone();
                 let binary = binary.clone();
                 let content_path = self.content_path.clone().unwrap();
                 let binary_parts = binary_parts.to_vec();
                 let pb = Arc::clone(&pb);
                 tokio::spawn(async move {
-                    // we ignore the ones we already created
-                    if let Some(binary_part) = binary_parts.iter().find(|x| x.index as u64 == index)
-                    {
-                        if matches!(binary_part.state, BinaryPartState::Valid) {
-                            pb.inc(binary_part.size);
-                            return;
-                        }
-                    }
+                    let file_position = binary_part_size * (index - 1);
 
-                    // we want to open the file in each thread, this is due to concurrency issues
-                    // when using `Seek` from different threads theres a race condition in the data
-                    let mut file = fs::File::open(&content_path).unwrap();
+                    let buffer =
+                        Self::read_binary_part(&content_path, file_position, binary_part_size)
+                            .context(FileSnafu)?;
 
-                    let file_position = binary_part_size * (index - 1);
+                    let n = buffer.len() as u64;
 
-                    file.seek(io::SeekFrom::Start(file_position)).unwrap();
+                    if n == 0 {
+                        return Ok(());
+                    }
 
-                    let mut buffer = vec![0; binary_part_size.try_into().unwrap()];
+                    let hash = Sha256::digest(&buffer);
+                    let hash_hex = format!("{hash:x}");
+
+                    // skip parts a previous attempt already uploaded, re-uploading any
+                    // whose content differs from the local file
+                    if binary_parts.iter().any(|binary_part| {
+                        u64::from(binary_part.index) == index
+                            && is_uploaded(binary_part, n, &hash_hex)
+                    }) {
+                        pb.inc(n);
+                        return Ok(());
+                    }
 
-                    let n = file.read(&mut buffer[..]).unwrap();
+                    let hash_base64 = general_purpose::STANDARD.encode(hash);
+
+                    retry_with_backoff(
+                        || {
+                            Self::upload_binary_part(
+                                &client,
+                                &api,
+                                &binary,
+                                index,
+                                &hash_hex,
+                                &hash_base64,
+                                &buffer,
+                            )
+                        },
+                        max_retries,
+                        DEFAULT_BASE_DELAY,
+                        PartUploadError::is_retryable,
+                    )
+                    .await
+                    .map_err(|error| Error::BinaryPartUpload {
+                        index,
+                        reason: error.to_string(),
+                    })?;
 
-                    if n > 0 {
-                        let mut mut_buffer = vec![0; n];
+                    pb.inc(n);
 
-                        mut_buffer.copy_from_slice(&buffer[..n]);
+                    Ok(())
+                })
+            })
+            .buffer_unordered(self.concurrency.unwrap().into())
+            .collect::<Vec<_>>()
+            .await;
 
-                        let mut hasher = Sha256::new();
-                        let _ = io::copy(&mut &mut_buffer[..], &mut hasher).unwrap();
-                        let hash = hasher.finalize();
+        pb.finish_and_clear();
 
-                        // push those bytes to the server
-                        let create_command = crate::api::binary_parts::CreateCommand {
-                            binary_prn: binary.prn.clone(),
-                            expected_binary_size: binary.size,
-                            index: index as u16,
-                            hash: format!("{hash:x}"),
-                            api: Some(api),
-                            size: n as u64,
-                            binary_content_path: None,
-                        };
+        for result in results {
+            // a panicking upload task is a bug, surface it rather than carrying on
+            result.expect("binary part upload task panicked")?;
+        }
 
-                        let bin_part = create_command
-                            .run(global_options)
-                            .await
-                            .expect("Error while creating a binary part binary part")
-                            .expect("Cannot create a binary part");
+        Ok(())
+    }
 
-                        // do amazon request
-                        let body = Body::from(mut_buffer);
+    /// Makes one attempt at creating binary part `index` and uploading `buffer` to its
+    /// presigned URL.
+    async fn upload_binary_part(
+        client: &Client,
+        api: &Api,
+        binary: &Binary,
+        index: u64,
+        hash_hex: &str,
+        hash_base64: &str,
+        buffer: &[u8],
+    ) -> Result<(), PartUploadError> {
+        let params = CreateBinaryPartParams {
+            binary_prn: binary.prn.clone(),
+            index: index as u16,
+            expected_binary_size: binary.size.unwrap(),
+            hash: hash_hex.to_string(),
+            size: buffer.len() as u64,
+        };
 
-                        let hash_base64 = general_purpose::STANDARD.encode(hash);
+        let bin_part = match api.binary_parts().create(params).await {
+            Ok(Some(bin_part)) => bin_part,
+            Ok(None) => {
+                return Err(PartUploadError::Api(Error::EmptyResponse {
+                    context: "binary-parts create".to_string(),
+                }))
+            }
+            Err(source) => return Err(PartUploadError::Api(Error::Api { source })),
+        };
 
-                        let res = client
-                            .put(bin_part.binary_part.presigned_upload_url)
-                            .body(body)
-                            .header("x-amz-checksum-sha256", &hash_base64)
-                            .header("content-length", n)
-                            .header("content-type", "application/octet-stream")
-                            .send()
-                            .await
-                            .unwrap();
+        // do amazon request
+        let res = client
+            .put(bin_part.binary_part.presigned_upload_url)
+            .body(Body::from(buffer.to_vec()))
+            .header("x-amz-checksum-sha256", hash_base64)
+            .header("content-length", buffer.len())
+            .header("content-type", "application/octet-stream")
+            .send()
+            .await
+            .map_err(PartUploadError::Request)?;
+
+        if !(200..=201).contains(&res.status().as_u16()) {
+            return Err(PartUploadError::Status(res.status()));
+        }
 
-                        pb.inc(n.try_into().unwrap());
+        Ok(())
+    }
 
-                        if !(200..=201).contains(&res.status().as_u16()) {
-                            panic!("Wasn't able to upload binary to amazon S3")
-                        };
-                    };
-                })
-            })
-            .buffer_unordered(self.concurrency.unwrap().into());
+    /// Reads a single binary part from disk, starting at `offset` and up to `size` bytes.
+    ///
+    /// The file is opened on each call, as sharing a `Seek`able handle across concurrent
+    /// uploads races on the file position. Only the part itself is held in memory.
+    fn read_binary_part(content_path: &str, offset: u64, size: u64) -> io::Result<Vec<u8>> {
+        let mut file = fs::File::open(content_path)?;
 
-        let _ = result.collect::<Vec<_>>().await;
+        file.seek(io::SeekFrom::Start(offset))?;
 
-        pb.finish_and_clear();
+        // `read` may return fewer bytes than requested, `read_to_end` keeps reading until
+        // the part is full or the end of the file is reached
+        let mut buffer = Vec::with_capacity(size.try_into().unwrap_or_default());
+        file.take(size).read_to_end(&mut buffer)?;
 
-        Ok(())
+        Ok(buffer)
     }
 
     async fn get_binary_parts(
@@ -566,35 +719,41 @@ impl CreateCommand {
             Self::get_organization_prn_from_prn(self.artifact_version_prn.clone());
 
         let (size, hash) = if let Some(content_path) = &self.content_path {
-            eprintln!("Hashing binary...");
-            let mut file = fs::File::open(content_path).context(NonExistingPathSnafu {
-                path: &content_path,
-            })?;
-            let mut hasher = Sha256::new();
-            let _ = io::copy(&mut file, &mut hasher).unwrap();
-            let hash = hasher.finalize();
-            (file.metadata().unwrap().len(), format!("{hash:x}"))
+            print_status!("Hashing binary...");
+            hash_file(content_path, self.no_progress)?
         } else {
             (self.size.unwrap(), self.hash.clone().unwrap())
         };
 
-        let list_params = ListBinariesParams {
-            search: format!(
-                "organization_prn:'{}' and target:'{}' and artifact_version_prn:'{}'",
-                organization_prn, self.target, self.artifact_version_prn
-            ),
-            limit: None,
-            order: None,
-            page: None,
-        };
+        let search = format!(
+            "organization_prn:'{}' and target:'{}' and artifact_version_prn:'{}'",
+            organization_prn, self.target, self.artifact_version_prn
+        );
+
+        let binaries = retry_with_backoff(
+            || async {
+                let list_params = ListBinariesParams {
+                    search: search.clone(),
+                    limit: None,
+                    order: None,
+                    page: None,
+                };
 
-        match api.binaries().list(list_params).await.context(ApiSnafu)? {
+                api.binaries().list(list_params).await.context(ApiSnafu)
+            },
+            self.max_retries.into(),
+            DEFAULT_BASE_DELAY,
+            is_rate_limited,
+        )
+        .await?;
+
+        match binaries {
             Some(ListBinariesResponse {
                 binaries,
                 next_page: _,
             }) if binaries.len() == 1 => {
                 // we found the binary, do as it was created
-                eprintln!("Binary already exists...");
+                print_status!("Binary already exists...");
                 let binary = binaries.first().unwrap().clone();
 
                 // is we get a binary, check the hash with out local hash
@@ -612,7 +771,7 @@ impl CreateCommand {
             }
 
             _ => {
-                eprintln!("Creating binary...");
+                print_status!("Creating binary...");
                 let custom_metadata =
                     if let Some(custom_metadata_path) = self.custom_metadata_path.clone() {
                         fs::read_to_string(&custom_metadata_path)
@@ -625,17 +784,25 @@ impl CreateCommand {
                     };
 
                 // create the binary
-                let params = CreateBinaryParams {
-                    artifact_version_prn: self.artifact_version_prn.clone(),
-                    custom_metadata: maybe_json(custom_metadata),
-                    description: self.description.clone(),
-                    hash,
-                    id: self.id.clone(),
-                    size,
-                    target: self.target.clone(),
-                };
+                retry_with_backoff(
+                    || async {
+                        let params = CreateBinaryParams {
+                            artifact_version_prn: self.artifact_version_prn.clone(),
+                            custom_metadata: maybe_json(custom_metadata.clone()),
+                            description: self.description.clone(),
+                            hash: hash.clone(),
+                            id: self.id.clone(),
+                            size,
+                            target: self.target.clone(),
+                        };
 
-                Ok(api.binaries().create(params).await.context(ApiSnafu)?)
+                        api.binaries().create(params).await.context(ApiSnafu)
+                    },
+                    self.max_retries.into(),
+                    DEFAULT_BASE_DELAY,
+                    is_rate_limited,
+                )
+                .await
             }
         }
     }
@@ -651,23 +818,35 @@ impl CreateCommand {
             .change_binary_status(ArgBinaryState::Uploadable, binary, api)
             .await?;
 
-        let update_command = UpdateCommand {
-            prn: binary.prn.clone(),
-            custom_metadata: None,
-            description: None,
-            hash: Some(hash),
-            size: Some(size),
-            state: None,
-            api: Some(api.clone()),
-        };
-
         // update hash and size
-        let binary = match update_command
-            .run(self.global_options.clone().unwrap())
-            .await?
-        {
+        let response = retry_with_backoff(
+            || async {
+                let update_command = UpdateCommand {
+                    prn: binary.prn.clone(),
+                    custom_metadata: None,
+                    description: None,
+                    hash: Some(hash.clone()),
+                    size: Some(size),
+                    state: None,
+                    api: Some(api.clone()),
+                };
+
+                update_command
+                    .run(self.global_options.clone().unwrap())
+                    .await
+            },
+            self.max_retries.into(),
+            DEFAULT_BASE_DELAY,
+            is_rate_limited,
+        )
+        .await?;
+
+        let binary = match response {
             Some(UpdateBinaryResponse { binary }) => binary,
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "binaries update",
+            }
+            .fail()?,
         };
 
         Ok(binary)
@@ -687,40 +866,79 @@ impl Command<CreateCommand> {
     async fn run(mut self, global_options: GlobalOptions) -> Result<(), Error> {
         match self.inner.run(global_options).await? {
             Some(binary) => print_json!(&binary),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "binaries create",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
 
+/// List binaries.
+///
+/// Instead of writing a `--search` query, binaries can be filtered with the options below. When both are given they are combined with `and`.
 #[derive(Parser, Debug)]
+#[command(mut_arg("search", |arg| arg.required(false).required_unless_present_any(["artifact_version_prn", "target", "state", "since", "until"])))]
 pub struct ListCommand {
     #[clap(flatten)]
     list_args: ListArgs,
+
+    /// Only list binaries of this artifact version.
+    #[arg(
+        long,
+        value_parser = PRNValueParser::new(PRNType::ArtifactVersion)
+    )]
+    artifact_version_prn: Option<String>,
+
+    /// Only list binaries with this target.
+    #[arg(long)]
+    target: Option<String>,
+
+    /// Only list binaries in this state.
+    #[arg(long, value_enum)]
+    state: Option<ArgBinaryState>,
 }
 
 impl Command<ListCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let params = ListBinariesParams {
-            limit: self.inner.list_args.limit,
-            order: self.inner.list_args.order,
-            search: self.inner.list_args.search,
-            page: self.inner.list_args.page,
-        };
-
         let api = Api::new(ApiOptions {
             api_key: global_options.api_key.unwrap(),
             endpoint: global_options.base_url,
             ca_bundle_path: global_options.ca_path,
         });
 
-        match api.binaries().list(params).await.context(ApiSnafu)? {
-            Some(binary) => print_json!(&binary),
-            None => panic!(),
-        }
+        let list_args = &self.inner.list_args;
 
-        Ok(())
+        let state = self
+            .inner
+            .state
+            .and_then(|state| clap::ValueEnum::to_possible_value(&state))
+            .map(|state| state.get_name().to_string());
+
+        let search = list_args.search_query(&[
+            (
+                "artifact_version_prn",
+                self.inner.artifact_version_prn.as_deref(),
+            ),
+            ("target", self.inner.target.as_deref()),
+            ("state", state.as_deref()),
+        ]);
+
+        list_args
+            .print_pages("binaries list", |page| {
+                let params = ListBinariesParams {
+                    limit: list_args.limit,
+                    order: list_args.order.clone(),
+                    search: search.clone(),
+                    page,
+                };
+                let api = api.clone();
+
+                async move { api.binaries().list(params).await }
+            })
+            .await
     }
 }
 
@@ -733,6 +951,9 @@ pub struct GetCommand {
     )]
     prn: String,
 
+    #[clap(flatten)]
+    get_args: GetArgs,
+
     #[clap(skip)]
     pub api: Option<Api>,
 }
@@ -757,9 +978,15 @@ impl GetCommand {
 
 impl Command<GetCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        match self.inner.run(global_options).await? {
-            Some(binary) => print_json!(&binary),
-            None => panic!(),
+        let mut command = self.inner;
+        let get_args = std::mem::take(&mut command.get_args);
+
+        match command.run(global_options).await? {
+            Some(binary) => get_args.print(&binary)?,
+            None => EmptyResponseSnafu {
+                context: "binaries get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -831,13 +1058,177 @@ impl Command<UpdateCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
         match self.inner.run(global_options).await? {
             Some(binary) => print_json!(&binary),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "binaries update",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
 
+/// Check that a local file matches the content registered for a binary.
+///
+/// Compares the SHA256 hash and size of the local file with the binary's, and exits with a non-zero status when they differ.
+#[derive(Parser, Debug)]
+pub struct VerifyCommand {
+    /// The PRN of the binary to verify against.
+    #[arg(
+        long,
+        value_parser = PRNValueParser::new(PRNType::Binary)
+    )]
+    prn: String,
+
+    /// The path to the local file to verify.
+    #[arg(long)]
+    content_path: String,
+
+    /// Do not display a progress bar while hashing. The progress bar is also hidden when stderr is not a terminal.
+    #[arg(long)]
+    no_progress: bool,
+}
+
+impl Command<VerifyCommand> {
+    async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let (local_size, local_hash) = hash_file(&self.inner.content_path, self.inner.no_progress)?;
+
+        let api = Api::new(ApiOptions {
+            api_key: global_options.api_key.unwrap(),
+            endpoint: global_options.base_url,
+            ca_bundle_path: global_options.ca_path,
+        });
+
+        let binary = match api
+            .binaries()
+            .get(GetBinaryParams {
+                prn: self.inner.prn.clone(),
+            })
+            .await
+            .context(ApiSnafu)?
+        {
+            Some(GetBinaryResponse { binary }) => binary,
+            None => EmptyResponseSnafu {
+                context: "binaries verify",
+            }
+            .fail()?,
+        };
+
+        let hash_matches = binary.hash.as_deref() == Some(local_hash.as_str());
+        let size_matches = binary.size == Some(local_size);
+
+        print_json!(&serde_json::json!({
+            "prn": binary.prn,
+            "hash": {
+                "local": local_hash,
+                "remote": binary.hash,
+                "match": hash_matches,
+            },
+            "size": {
+                "local": local_size,
+                "remote": binary.size,
+                "match": size_matches,
+            },
+        }));
+
+        if hash_matches && size_matches {
+            Ok(())
+        } else {
+            Err(Error::BinaryMismatch {
+                prn: self.inner.prn,
+            })
+        }
+    }
+}
+
+/// The smallest part size the API accepts.
+const MIN_BINARY_PART_SIZE: u64 = 5_242_880;
+
+/// The largest part size the API accepts.
+const MAX_BINARY_PART_SIZE: u64 = 49_999_999_999;
+
+/// The number of parts `--auto-part-size` aims to stay within.
+const AUTO_PART_COUNT: u64 = 1000;
+
+/// Picks the smallest part size, in whole MiB, that splits `file_size` bytes into at most
+/// [`AUTO_PART_COUNT`] parts, within the sizes the API accepts.
+fn auto_binary_part_size(file_size: u64) -> u64 {
+    file_size
+        .div_ceil(AUTO_PART_COUNT)
+        .next_multiple_of(1024 * 1024)
+        .clamp(MIN_BINARY_PART_SIZE, MAX_BINARY_PART_SIZE)
+}
+
+/// Returns the size in bytes and the lowercase hex SHA256 hash of the file at `content_path`,
+/// showing a progress bar while hashing unless `no_progress` is set.
+fn hash_file(content_path: &str, no_progress: bool) -> Result<(u64, String), Error> {
+    let file = fs::File::open(content_path).context(NonExistingPathSnafu {
+        path: &content_path,
+    })?;
+    let size = file.metadata().context(FileSnafu)?.len();
+
+    let pb = progress_bar(size, no_progress);
+    let mut hasher = Sha256::new();
+    let copied = io::copy(&mut pb.wrap_read(file), &mut hasher);
+    pb.finish_and_clear();
+    copied.context(FileSnafu)?;
+
+    let hash = hasher.finalize();
+    Ok((size, format!("{hash:x}")))
+}
+
+/// A progress bar over `total` bytes, hidden under `--no-progress` or `--quiet`, or when
+/// stderr is not a terminal.
+fn progress_bar(total: u64, no_progress: bool) -> ProgressBar {
+    let draw_target = if no_progress || crate::utils::quiet() || !io::stderr().is_terminal() {
+        ProgressDrawTarget::hidden()
+    } else {
+        ProgressDrawTarget::stderr()
+    };
+    let pb = ProgressBar::with_draw_target(Some(total), draw_target);
+    pb.set_style(ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} {bytes_per_sec} ({eta})")
+        .unwrap()
+        .with_key("eta", |state: &ProgressState, w: &mut dyn std::fmt::Write| write!(w, "{:.1}s", state.eta().as_secs_f64()).unwrap())
+        .progress_chars("#>-"));
+    pb
+}
+
+/// Why a single attempt at uploading a binary part failed.
+enum PartUploadError {
+    /// Creating the binary part through the API failed.
+    Api(Error),
+    /// The upload to the presigned URL couldn't be sent.
+    Request(reqwest::Error),
+    /// The presigned URL rejected the upload.
+    Status(StatusCode),
+}
+
+impl PartUploadError {
+    /// Timeouts, connection failures, rate limiting and server errors are retried, any other
+    /// rejection would only fail again.
+    fn is_retryable(&self) -> bool {
+        match self {
+            Self::Api(error) => is_transient(error),
+            Self::Request(error) => error.is_timeout() || error.is_connect() || error.is_request(),
+            Self::Status(status) => {
+                *status == StatusCode::REQUEST_TIMEOUT
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+                    || status.is_server_error()
+            }
+        }
+    }
+}
+
+impl fmt::Display for PartUploadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Api(error) => write!(f, "{error}"),
+            Self::Request(error) => write!(f, "upload request failed: {error}"),
+            Self::Status(status) => write!(f, "upload was rejected with {status}"),
+        }
+    }
+}
+
 #[derive(clap::ValueEnum, Clone, Debug)]
 pub enum ArgBinaryState {
     Destroyed,
@@ -860,3 +1251,25 @@ impl From<ArgBinaryState> for BinaryState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_binary_part_size_keeps_part_count_bounded() {
+        // small files keep the minimum part size
+        assert_eq!(auto_binary_part_size(0), MIN_BINARY_PART_SIZE);
+        assert_eq!(
+            auto_binary_part_size(100 * 1024 * 1024),
+            MIN_BINARY_PART_SIZE
+        );
+
+        // a 20 GiB file is split into at most 1000 parts of whole MiB
+        let file_size = 20 * 1024 * 1024 * 1024;
+        let part_size = auto_binary_part_size(file_size);
+        assert_eq!(part_size % (1024 * 1024), 0);
+        assert!(file_size.div_ceil(part_size) <= AUTO_PART_COUNT);
+        assert_eq!(part_size, 21 * 1024 * 1024);
+    }
+}
@@ -3,23 +3,32 @@ use crate::print_json;
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
+use crate::FileSnafu;
 use crate::GlobalOptions;
+use crate::NonExistingPathSnafu;
 use clap::Parser;
+use peridio_sdk::api::binary_parts::BinaryPartState;
 use peridio_sdk::api::binary_parts::CreateBinaryPartParams;
 use peridio_sdk::api::binary_parts::CreateBinaryPartResponse;
+use peridio_sdk::api::binary_parts::ListBinaryPart;
 use peridio_sdk::api::binary_parts::ListBinaryPartsParams;
 use peridio_sdk::api::binary_parts::ListBinaryPartsResponse;
 use peridio_sdk::api::Api;
 use peridio_sdk::api::ApiOptions;
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use snafu::ResultExt;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug)]
 pub enum BinaryPartsCommand {
     Create(Command<CreateCommand>),
     List(Command<ListCommand>),
+    Missing(Command<MissingCommand>),
 }
 
 impl BinaryPartsCommand {
@@ -27,6 +36,7 @@ impl BinaryPartsCommand {
         match self {
             Self::Create(cmd) => cmd.run(global_options).await,
             Self::List(cmd) => cmd.run(global_options).await,
+            Self::Missing(cmd) => cmd.run(global_options).await,
         }
     }
 }
@@ -108,7 +118,10 @@ impl Command<CreateCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
         match self.inner.run(global_options).await? {
             Some(binary_part) => print_json!(&binary_part),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "binary-parts create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -154,9 +167,99 @@ impl Command<ListCommand> {
     pub async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
         match self.inner.run(global_options).await? {
             Some(binary_part) => print_json!(&binary_part),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "binary-parts list",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
+
+/// Print which parts of a binary still need to be uploaded.
+///
+/// The file at `--content-path` is split into parts the way `binaries create` does, and each is compared to the binary's existing parts. A part is missing when it doesn't exist yet, isn't valid, or its size or hash differ from the local one.
+#[derive(Parser, Debug)]
+pub struct MissingCommand {
+    /// The PRN of the binary whose parts to inspect.
+    #[arg(
+        long,
+        value_parser = PRNValueParser::new(PRNType::Binary)
+    )]
+    binary_prn: String,
+
+    /// The path to the file being uploaded as the binary's content.
+    #[arg(long)]
+    content_path: PathBuf,
+
+    /// The part size the upload was started with.
+    #[arg(
+        long,
+        alias = "part-size",
+        default_value = "5242880",
+        value_parser = clap::value_parser!(u64).range(5242880..50000000000),
+    )]
+    binary_part_size: u64,
+}
+
+impl Command<MissingCommand> {
+    async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let local_parts = hash_parts(&self.inner.content_path, self.inner.binary_part_size)?;
+
+        let list_command = ListCommand {
+            binary_prn: self.inner.binary_prn.clone(),
+            api: None,
+        };
+
+        let binary_parts = match list_command.run(global_options).await? {
+            Some(response) => response.binary_parts,
+            None => EmptyResponseSnafu {
+                context: "binary-parts list",
+            }
+            .fail()?,
+        };
+
+        let missing: Vec<u64> = local_parts
+            .iter()
+            .zip(1..)
+            .filter(|((size, hash), index)| {
+                !binary_parts
+                    .iter()
+                    .any(|part| u64::from(part.index) == *index && is_uploaded(part, *size, hash))
+            })
+            .map(|(_, index)| index)
+            .collect();
+
+        print_json!(&json!({
+            "binary_prn": self.inner.binary_prn,
+            "parts": local_parts.len(),
+            "missing": missing,
+        }));
+
+        Ok(())
+    }
+}
+
+/// Whether `part` already holds a valid upload of a local part of `size` bytes whose SHA256
+/// is the lowercase hex `hash`.
+pub(crate) fn is_uploaded(part: &ListBinaryPart, size: u64, hash: &str) -> bool {
+    matches!(part.state, BinaryPartState::Valid) && part.size == size && part.hash == hash
+}
+
+/// Returns the size and lowercase hex SHA256 of each `part_size` part of the file at `path`.
+fn hash_parts(path: &Path, part_size: u64) -> Result<Vec<(u64, String)>, Error> {
+    let mut file = fs::File::open(path).context(NonExistingPathSnafu { path })?;
+    let mut parts = Vec::new();
+
+    loop {
+        let mut hasher = Sha256::new();
+        let size = io::copy(&mut (&mut file).take(part_size), &mut hasher).context(FileSnafu)?;
+
+        if size == 0 {
+            return Ok(parts);
+        }
+
+        parts.push((size, format!("{:x}", hasher.finalize())));
+    }
+}
@@ -3,18 +3,17 @@ use std::io;
 
 use super::Command;
 use crate::print_json;
+use crate::utils::signing;
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
 use crate::utils::Style;
 use crate::utils::StyledStr;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use crate::NonExistingPathSnafu;
 use clap::Parser;
-use ed25519_dalek::pkcs8::DecodePrivateKey;
-use ed25519_dalek::Signer;
-use ed25519_dalek::SigningKey;
 use peridio_sdk::api::binary_signatures::CreateBinarySignatureParams;
 use peridio_sdk::api::binary_signatures::CreateBinarySignatureResponse;
 use peridio_sdk::api::binary_signatures::DeleteBinarySignatureParams;
@@ -58,7 +57,7 @@ pub struct CreateCommand {
     pub binary_content_path: Option<String>,
     /// The signature of the binary content.
     ///
-    /// The hex encoded Ed25519 signature of the SHA256 hash of the binary content. To avoid computing this yourself, you can use the --binary-content-path option.
+    /// The hex encoded Ed25519 or ECDSA P-256 signature of the SHA256 hash of the binary content. To avoid computing this yourself, you can use the --binary-content-path option.
     #[arg(
         long,
         short = 'g',
@@ -203,7 +202,6 @@ impl CreateCommand {
             fs::read_to_string(&signing_key_private_path).context(NonExistingPathSnafu {
                 path: &signing_key_private_path,
             })?;
-        let signing_key: SigningKey = SigningKey::from_pkcs8_pem(&signing_key_private).unwrap();
 
         let hash = if let Some(hash) = binary_content_hash {
             hash
@@ -215,9 +213,7 @@ impl CreateCommand {
             format!("{hash:x}")
         };
 
-        let signed_hash = signing_key.sign(hash.as_bytes());
-
-        Ok(format!("{signed_hash:X}"))
+        signing::sign(&signing_key_private, hash.as_bytes())
     }
 }
 
@@ -225,7 +221,10 @@ impl Command<CreateCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
         match self.inner.run(global_options).await? {
             Some(binary_signature) => print_json!(&binary_signature),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "binary-signatures create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -261,7 +260,10 @@ impl Command<DeleteCommand> {
             .context(ApiSnafu)?
         {
             Some(binary_signature) => print_json!(&binary_signature),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "binary-signatures delete",
+            }
+            .fail()?,
         }
 
         Ok(())
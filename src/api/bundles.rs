@@ -1,9 +1,11 @@
 use super::Command;
+use crate::api::get::GetArgs;
 use crate::api::list::ListArgs;
 use crate::print_json;
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use clap::Parser;
@@ -72,7 +74,10 @@ impl Command<CreateCommand> {
 
         match api.bundles().create(params).await.context(ApiSnafu)? {
             Some(bundle) => print_json!(&bundle),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "bundles create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -87,25 +92,27 @@ pub struct ListCommand {
 
 impl Command<ListCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let params = ListBundlesParams {
-            limit: self.inner.list_args.limit,
-            order: self.inner.list_args.order,
-            search: self.inner.list_args.search,
-            page: self.inner.list_args.page,
-        };
-
         let api = Api::new(ApiOptions {
             api_key: global_options.api_key.unwrap(),
             endpoint: global_options.base_url,
             ca_bundle_path: global_options.ca_path,
         });
 
-        match api.bundles().list(params).await.context(ApiSnafu)? {
-            Some(bundle) => print_json!(&bundle),
-            None => panic!(),
-        }
-
-        Ok(())
+        let list_args = &self.inner.list_args;
+
+        list_args
+            .print_pages("bundles list", |page| {
+                let params = ListBundlesParams {
+                    limit: list_args.limit,
+                    order: list_args.order.clone(),
+                    search: list_args.search_query(&[]),
+                    page,
+                };
+                let api = api.clone();
+
+                async move { api.bundles().list(params).await }
+            })
+            .await
     }
 }
 
@@ -117,6 +124,9 @@ pub struct GetCommand {
         value_parser = PRNValueParser::new(PRNType::Bundle)
     )]
     prn: String,
+
+    #[clap(flatten)]
+    get_args: GetArgs,
 }
 
 impl Command<GetCommand> {
@@ -132,8 +142,11 @@ impl Command<GetCommand> {
         });
 
         match api.bundles().get(params).await.context(ApiSnafu)? {
-            Some(bundle) => print_json!(&bundle),
-            None => panic!(),
+            Some(bundle) => self.inner.get_args.print(&bundle)?,
+            None => EmptyResponseSnafu {
+                context: "bundles get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -169,7 +182,10 @@ impl Command<UpdateCommand> {
 
         match api.bundles().update(params).await.context(ApiSnafu)? {
             Some(response) => print_json!(&response),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "bundles update",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -1,6 +1,10 @@
+use super::x509;
 use super::Command;
-use crate::utils::{PRNType, PRNValueParser};
-use crate::{print_json, ApiSnafu, Error, GlobalOptions, NonExistingPathSnafu};
+use crate::utils::{PRNType, PRNValueParser, Style, StyledStr};
+use crate::{
+    print_json, ApiSnafu, EmptyResponseSnafu, Error, GlobalOptions, JsonSerializationSnafu,
+    MissingFieldSnafu, NonExistingPathSnafu,
+};
 use base64::{engine::general_purpose, Engine as _};
 use clap::Parser;
 use peridio_sdk::api::ca_certificates::CaCertificateJitp;
@@ -14,6 +18,7 @@ use peridio_sdk::api::{Api, ApiOptions};
 use snafu::ResultExt;
 use std::fs;
 use std::path::PathBuf;
+use time::OffsetDateTime;
 
 #[derive(Parser, Debug)]
 pub enum CaCertificatesCommand {
@@ -23,6 +28,7 @@ pub enum CaCertificatesCommand {
     List(Command<ListCommand>),
     Update(Command<UpdateCommand>),
     CreateVerificationCode(Command<CreateVerificationCodeCommand>),
+    Register(Command<RegisterCommand>),
 }
 
 impl CaCertificatesCommand {
@@ -34,6 +40,7 @@ impl CaCertificatesCommand {
             Self::List(cmd) => cmd.run(global_options).await,
             Self::Update(cmd) => cmd.run(global_options).await,
             Self::CreateVerificationCode(cmd) => cmd.run(global_options).await,
+            Self::Register(cmd) => cmd.run(global_options).await,
         }
     }
 }
@@ -45,8 +52,25 @@ pub struct CreateCommand {
     certificate_path: PathBuf,
 
     /// The path of the verification certificate.
-    #[arg(long, short = 'v')]
-    verification_certificate_path: PathBuf,
+    #[arg(
+        long,
+        short = 'v',
+        conflicts_with = "verification_code",
+        required_unless_present = "verification_code"
+    )]
+    verification_certificate_path: Option<PathBuf>,
+
+    /// A verification code from create-verification-code to generate the verification certificate for, instead of passing one with --verification-certificate-path.
+    #[arg(
+        long,
+        requires = "private_key_path",
+        value_parser = clap::builder::NonEmptyStringValueParser::new()
+    )]
+    verification_code: Option<String>,
+
+    /// The path of the CA certificate's private key, used to sign the generated verification certificate.
+    #[arg(long, requires = "verification_code")]
+    private_key_path: Option<PathBuf>,
 
     /// An arbitrary string attached to the resource. Often useful for displaying to users.
     #[arg(long)]
@@ -82,10 +106,20 @@ impl Command<CreateCommand> {
             fs::read_to_string(&self.inner.certificate_path).context(NonExistingPathSnafu {
                 path: &self.inner.certificate_path,
             })?;
-        let verification_cert = fs::read_to_string(self.inner.verification_certificate_path)
-            .context(NonExistingPathSnafu {
-                path: &self.inner.certificate_path,
-            })?;
+        let verification_cert = if let (Some(verification_code), Some(private_key_path)) =
+            (&self.inner.verification_code, &self.inner.private_key_path)
+        {
+            let (signer_key, signer_cert) =
+                x509::signer_from_paths(private_key_path, &self.inner.certificate_path)?;
+
+            x509::verification_certificate(verification_code, &signer_key, &signer_cert)?.pem()
+        } else {
+            let verification_certificate_path = self.inner.verification_certificate_path.unwrap();
+
+            fs::read_to_string(&verification_certificate_path).context(NonExistingPathSnafu {
+                path: &verification_certificate_path,
+            })?
+        };
 
         let cert_base64 = general_purpose::STANDARD.encode(cert);
         let verification_cert_base64 = general_purpose::STANDARD.encode(verification_cert);
@@ -127,7 +161,10 @@ impl Command<CreateCommand> {
             .context(ApiSnafu)?
         {
             Some(ca_certificate) => print_json!(&ca_certificate),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "ca-certificates create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -189,7 +226,10 @@ impl Command<GetCommand> {
 
         match api.ca_certificates().get(params).await.context(ApiSnafu)? {
             Some(ca_certificate) => print_json!(&ca_certificate),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "ca-certificates get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -213,7 +253,10 @@ impl Command<ListCommand> {
 
         match api.ca_certificates().list(params).await.context(ApiSnafu)? {
             Some(ca_certificates) => print_json!(&ca_certificates),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "ca-certificates list",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -300,7 +343,10 @@ impl Command<UpdateCommand> {
             .context(ApiSnafu)?
         {
             Some(ca_certificate) => print_json!(&ca_certificate),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "ca-certificates update",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -332,7 +378,162 @@ impl Command<CreateVerificationCodeCommand> {
             .context(ApiSnafu)?
         {
             Some(verification_code) => print_json!(&verification_code),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "ca-certificates create-verification-code",
+            }
+            .fail()?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Register a CA certificate in one step.
+///
+/// Takes an existing CA certificate and private key, or generates a self-signed CA with --generate. A verification code is then requested, a verification certificate for it is signed with the CA's private key, and both certificates are uploaded.
+#[derive(Parser, Debug)]
+pub struct RegisterCommand {
+    /// The path of an existing CA certificate to register.
+    #[arg(
+        long,
+        short = 'c',
+        requires = "private_key_path",
+        required_unless_present = "generate"
+    )]
+    certificate_path: Option<PathBuf>,
+
+    /// The path of the private key of the CA certificate, used to sign the verification certificate.
+    #[arg(long, requires = "certificate_path")]
+    private_key_path: Option<PathBuf>,
+
+    /// Generate a new self-signed CA certificate and private key instead of registering an existing one.
+    #[arg(
+        long,
+        conflicts_with = "certificate_path",
+        requires_all = ["common_name", "end_date"]
+    )]
+    generate: bool,
+
+    /// The Common Name (CN) of the generated CA certificate.
+    #[arg(long, requires = "generate")]
+    common_name: Option<String>,
+
+    /// The start date of the generated CA certificate's validity period (format: YYYY-MM-DD or an RFC 3339 datetime). Defaults to now.
+    #[arg(long, requires = "generate")]
+    start_date: Option<String>,
+
+    /// The end date of the generated CA certificate's validity period (format: YYYY-MM-DD or an RFC 3339 datetime).
+    #[arg(long, requires = "generate")]
+    end_date: Option<String>,
+
+    /// Directory to save the generated CA certificate and private key to (defaults to current working directory)
+    #[arg(long, requires = "generate")]
+    out: Option<PathBuf>,
+
+    /// An arbitrary string attached to the resource. Often useful for displaying to users.
+    #[arg(long)]
+    description: Option<String>,
+}
+
+impl Command<RegisterCommand> {
+    async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let (cert_pem, signer_key, signer_cert, generated_key_path) =
+            if let (Some(certificate_path), Some(private_key_path)) =
+                (&self.inner.certificate_path, &self.inner.private_key_path)
+            {
+                let cert_pem =
+                    fs::read_to_string(certificate_path).context(NonExistingPathSnafu {
+                        path: certificate_path,
+                    })?;
+                let (signer_key, signer_cert) =
+                    x509::signer_from_paths(private_key_path, certificate_path)?;
+
+                (cert_pem, signer_key, signer_cert, None)
+            } else {
+                let common_name = self.inner.common_name.unwrap();
+                let not_before = match &self.inner.start_date {
+                    Some(start_date) => x509::parse_date(start_date)?,
+                    None => OffsetDateTime::now_utc(),
+                };
+                let not_after = x509::parse_date(&self.inner.end_date.unwrap())?;
+
+                let (cert, key_pair) = x509::self_signed_ca(&common_name, not_before, not_after)?;
+                let (_, private_key) = x509::write_certificate_and_key(
+                    self.inner.out,
+                    &common_name,
+                    &cert,
+                    &key_pair,
+                )?;
+
+                (cert.pem(), key_pair, cert, Some(private_key))
+            };
+
+        let organization_name = global_options.organization_name.unwrap();
+
+        let api = Api::new(ApiOptions {
+            api_key: global_options.api_key.unwrap(),
+            endpoint: global_options.base_url,
+            ca_bundle_path: global_options.ca_path,
+        });
+
+        let verification_code = match api
+            .ca_certificates()
+            .create_verification_code(CreateVerificationCodeParams {
+                organization_name: organization_name.clone(),
+            })
+            .await
+            .context(ApiSnafu)?
+        {
+            Some(response) => serde_json::to_value(&response).context(JsonSerializationSnafu)?,
+            None => EmptyResponseSnafu {
+                context: "ca-certificates create-verification-code",
+            }
+            .fail()?,
+        };
+
+        let Some(verification_code) = verification_code["verification_code"].as_str() else {
+            return MissingFieldSnafu {
+                context: "ca-certificates create-verification-code",
+                field: "verification_code",
+            }
+            .fail();
+        };
+
+        let verification_cert =
+            x509::verification_certificate(verification_code, &signer_key, &signer_cert)?;
+
+        let params = CreateCaCertificateParams {
+            organization_name,
+            certificate: general_purpose::STANDARD.encode(cert_pem),
+            verification_certificate: general_purpose::STANDARD.encode(verification_cert.pem()),
+            description: self.inner.description,
+            jitp: None,
+        };
+
+        match api
+            .ca_certificates()
+            .create(params)
+            .await
+            .context(ApiSnafu)?
+        {
+            Some(ca_certificate) => print_json!(&ca_certificate),
+            None => EmptyResponseSnafu {
+                context: "ca-certificates create",
+            }
+            .fail()?,
+        }
+
+        if let Some(private_key) = generated_key_path {
+            let mut warning = StyledStr::new();
+            warning.push_str(Some(Style::Warning), "warning: ".to_string());
+            warning.push_str(
+                None,
+                format!(
+                    "Store the CA private key at {} securely, anyone holding it can issue device certificates for your organization.",
+                    private_key.display()
+                ),
+            );
+            let _ = warning.print_err();
         }
 
         Ok(())
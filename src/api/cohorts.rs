@@ -1,17 +1,24 @@
+use std::collections::{HashMap, HashSet};
+
 use super::Command;
-use crate::api::list::ListArgs;
+use crate::api::get::GetArgs;
+use crate::api::list::{fetch_pages, ListArgs};
 use crate::print_json;
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
+use crate::utils::{Style, StyledStr};
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use clap::Parser;
 use peridio_sdk::api::cohorts::{
     CreateCohortParams, GetCohortParams, ListCohortsParams, UpdateCohortParams,
 };
+use peridio_sdk::api::releases::ListReleasesParams;
 use peridio_sdk::api::Api;
 use peridio_sdk::api::ApiOptions;
+use serde_json::{json, Value};
 use snafu::ResultExt;
 
 #[derive(Parser, Debug)]
@@ -19,6 +26,7 @@ pub enum CohortsCommand {
     Create(Command<CreateCommand>),
     List(Command<ListCommand>),
     Get(Command<GetCommand>),
+    ReleasesGraph(Command<ReleasesGraphCommand>),
     Update(Command<UpdateCommand>),
 }
 
@@ -28,6 +36,7 @@ impl CohortsCommand {
             Self::Create(cmd) => cmd.run(global_options).await,
             Self::List(cmd) => cmd.run(global_options).await,
             Self::Get(cmd) => cmd.run(global_options).await,
+            Self::ReleasesGraph(cmd) => cmd.run(global_options).await,
             Self::Update(cmd) => cmd.run(global_options).await,
         }
     }
@@ -76,7 +85,10 @@ impl Command<CreateCommand> {
 
         match api.cohorts().create(params).await.context(ApiSnafu)? {
             Some(cohort) => print_json!(&cohort),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "cohorts create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -91,25 +103,27 @@ pub struct ListCommand {
 
 impl Command<ListCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let params = ListCohortsParams {
-            limit: self.inner.list_args.limit,
-            order: self.inner.list_args.order,
-            search: self.inner.list_args.search,
-            page: self.inner.list_args.page,
-        };
-
         let api = Api::new(ApiOptions {
             api_key: global_options.api_key.unwrap(),
             endpoint: global_options.base_url,
             ca_bundle_path: global_options.ca_path,
         });
 
-        match api.cohorts().list(params).await.context(ApiSnafu)? {
-            Some(cohort) => print_json!(&cohort),
-            None => panic!(),
-        }
+        let list_args = &self.inner.list_args;
 
-        Ok(())
+        list_args
+            .print_pages("cohorts list", |page| {
+                let params = ListCohortsParams {
+                    limit: list_args.limit,
+                    order: list_args.order.clone(),
+                    search: list_args.search_query(&[]),
+                    page,
+                };
+                let api = api.clone();
+
+                async move { api.cohorts().list(params).await }
+            })
+            .await
     }
 }
 
@@ -121,6 +135,9 @@ pub struct GetCommand {
         value_parser = PRNValueParser::new(PRNType::Cohort)
     )]
     prn: String,
+
+    #[clap(flatten)]
+    get_args: GetArgs,
 }
 
 impl Command<GetCommand> {
@@ -136,14 +153,163 @@ impl Command<GetCommand> {
         });
 
         match api.cohorts().get(params).await.context(ApiSnafu)? {
-            Some(cohort) => print_json!(&cohort),
-            None => panic!(),
+            Some(cohort) => self.inner.get_args.print(&cohort)?,
+            None => EmptyResponseSnafu {
+                context: "cohorts get",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
 
+/// Print the releases of a cohort in the order devices move through them.
+///
+/// The order is rebuilt by following each release's next_release_prn, starting from the release without a previous_release_prn. Releases that can't be reached that way and cycles in the graph are reported instead of followed.
+#[derive(Parser, Debug)]
+pub struct ReleasesGraphCommand {
+    /// The PRN of the cohort whose releases to order.
+    #[arg(
+        long,
+        value_parser = PRNValueParser::new(PRNType::Cohort)
+    )]
+    cohort_prn: String,
+
+    /// The maximum number of pages of releases to fetch.
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+    max_pages: u32,
+}
+
+impl Command<ReleasesGraphCommand> {
+    async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let api = Api::new(ApiOptions {
+            api_key: global_options.api_key.unwrap(),
+            endpoint: global_options.base_url,
+            ca_bundle_path: global_options.ca_path,
+        });
+
+        let search = format!("cohort_prn:'{}'", self.inner.cohort_prn);
+
+        let (releases, complete) = fetch_pages("releases list", self.inner.max_pages, |page| {
+            let params = ListReleasesParams {
+                limit: None,
+                order: None,
+                search: search.clone(),
+                page,
+            };
+            let api = api.clone();
+
+            async move { api.releases().list(params).await }
+        })
+        .await?;
+
+        if !complete {
+            warn(format!(
+                "Stopped after {} pages, the graph may be incomplete. Use --max-pages to fetch more.",
+                self.inner.max_pages
+            ));
+        }
+
+        let graph = release_graph(&releases);
+
+        if !graph.disconnected.is_empty() {
+            warn(format!(
+                "{} release(s) are not reachable from the first release of the cohort.",
+                graph.disconnected.len()
+            ));
+        }
+
+        if let Some(prn) = graph.cycle {
+            warn(format!("The release graph loops back to {prn}."));
+        }
+
+        print_json!(&json!({
+            "cohort_prn": self.inner.cohort_prn,
+            "releases": graph.ordered.iter().enumerate().map(|(position, release)| json!({
+                "position": position + 1,
+                "prn": release["prn"],
+                "name": release["name"],
+                "version": release["version"],
+                "phase_value": release["phase_value"],
+                "required": release["required"],
+                "disabled": release["disabled"],
+            })).collect::<Vec<_>>(),
+            "disconnected": graph.disconnected,
+            "cycle": graph.cycle,
+        }));
+
+        Ok(())
+    }
+}
+
+struct ReleaseGraph<'a> {
+    ordered: Vec<&'a Value>,
+    disconnected: Vec<&'a str>,
+    cycle: Option<&'a str>,
+}
+
+/// Orders `releases` by following `next_release_prn` from the release without a previous
+/// release, stopping when a release is visited twice.
+///
+/// When every release has a previous release the graph is one big cycle, so the walk starts
+/// from the first release instead and reports the cycle where it closes.
+fn release_graph(releases: &[Value]) -> ReleaseGraph<'_> {
+    let by_prn: HashMap<&str, &Value> = releases
+        .iter()
+        .filter_map(|release| Some((release["prn"].as_str()?, release)))
+        .collect();
+
+    let first = releases
+        .iter()
+        .find(|release| {
+            release["previous_release_prn"]
+                .as_str()
+                .filter(|prn| by_prn.contains_key(prn))
+                .is_none()
+        })
+        .or_else(|| releases.first());
+
+    let mut ordered = Vec::new();
+    let mut visited = HashSet::new();
+    let mut cycle = None;
+    let mut current = first;
+
+    while let Some(release) = current {
+        let prn = release["prn"].as_str().unwrap_or_default();
+
+        if !visited.insert(prn) {
+            cycle = Some(prn);
+            break;
+        }
+
+        ordered.push(release);
+
+        current = release["next_release_prn"]
+            .as_str()
+            .and_then(|next| by_prn.get(next).copied());
+    }
+
+    let disconnected = releases
+        .iter()
+        .filter_map(|release| release["prn"].as_str())
+        .filter(|prn| !visited.contains(prn))
+        .collect();
+
+    ReleaseGraph {
+        ordered,
+        disconnected,
+        cycle,
+    }
+}
+
+fn warn(message: String) {
+    let mut warning = StyledStr::new();
+    warning.push_str(Some(Style::Warning), "warning: ".to_string());
+    warning.push_str(None, message);
+    let _ = warning.print_err();
+}
+
 #[derive(Parser, Debug)]
 pub struct UpdateCommand {
     /// The PRN of the resource to update.
@@ -178,9 +344,89 @@ impl Command<UpdateCommand> {
 
         match api.cohorts().update(params).await.context(ApiSnafu)? {
             Some(cohort) => print_json!(&cohort),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "cohorts update",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(prn: &str, previous: Option<&str>, next: Option<&str>) -> Value {
+        json!({
+            "prn": prn,
+            "previous_release_prn": previous,
+            "next_release_prn": next,
+        })
+    }
+
+    fn prns<'a>(graph: &ReleaseGraph<'a>) -> Vec<&'a str> {
+        graph
+            .ordered
+            .iter()
+            .map(|release| release["prn"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn release_graph_orders_a_linear_chain() {
+        let releases = [
+            release("c", Some("b"), None),
+            release("a", None, Some("b")),
+            release("b", Some("a"), Some("c")),
+        ];
+        let graph = release_graph(&releases);
+
+        assert_eq!(prns(&graph), ["a", "b", "c"]);
+        assert!(graph.disconnected.is_empty());
+        assert_eq!(graph.cycle, None);
+    }
+
+    #[test]
+    fn release_graph_reports_releases_after_other_heads_as_disconnected() {
+        let releases = [
+            release("a", None, Some("b")),
+            release("b", Some("a"), None),
+            release("x", None, Some("y")),
+            release("y", Some("x"), None),
+        ];
+        let graph = release_graph(&releases);
+
+        assert_eq!(prns(&graph), ["a", "b"]);
+        assert_eq!(graph.disconnected, ["x", "y"]);
+        assert_eq!(graph.cycle, None);
+    }
+
+    #[test]
+    fn release_graph_detects_a_partial_cycle() {
+        let releases = [
+            release("a", None, Some("b")),
+            release("b", Some("a"), Some("c")),
+            release("c", Some("b"), Some("b")),
+        ];
+        let graph = release_graph(&releases);
+
+        assert_eq!(prns(&graph), ["a", "b", "c"]);
+        assert!(graph.disconnected.is_empty());
+        assert_eq!(graph.cycle, Some("b"));
+    }
+
+    #[test]
+    fn release_graph_detects_a_full_cycle() {
+        let releases = [
+            release("a", Some("b"), Some("b")),
+            release("b", Some("a"), Some("a")),
+        ];
+        let graph = release_graph(&releases);
+
+        assert_eq!(prns(&graph), ["a", "b"]);
+        assert!(graph.disconnected.is_empty());
+        assert_eq!(graph.cycle, Some("a"));
+    }
+}
@@ -0,0 +1,25 @@
+use std::io;
+
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
+
+use crate::Error;
+use crate::Program;
+
+#[derive(Parser, Debug)]
+pub struct CompletionsCommand {
+    /// The shell to generate the completion script for.
+    #[arg(long, value_enum)]
+    shell: Shell,
+}
+
+impl CompletionsCommand {
+    pub fn run(self) -> Result<(), Error> {
+        let mut command = Program::command();
+        let bin_name = command.get_name().to_string();
+
+        clap_complete::generate(self.shell, &mut command, bin_name, &mut io::stdout());
+
+        Ok(())
+    }
+}
@@ -1,87 +1,160 @@
 use std::fs;
-use std::io::BufWriter;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use super::Command;
 use crate::config::config_v1::ConfigV1;
 use crate::config::config_v2::ConfigV2;
+use crate::config::write_atomic;
+use crate::config::Config;
+use crate::print_json;
+use crate::print_status;
 use crate::utils::Style;
 use crate::utils::StyledStr;
+use crate::ConfigParseSnafu;
+use crate::ConfigReadSnafu;
 use crate::Error;
+use crate::FileSnafu;
 use crate::GlobalOptions;
+use crate::JsonSerializationSnafu;
 use clap::Parser;
-use directories::ProjectDirs;
+use serde_json::json;
+use snafu::ResultExt;
+use time::macros::format_description;
+use time::OffsetDateTime;
 
 #[derive(Parser, Debug)]
 pub enum ConfigCommand {
+    Path(Command<PathCommand>),
     Upgrade(Command<UpgradeCommand>),
 }
 
 impl ConfigCommand {
     pub async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
         match self {
+            Self::Path(cmd) => cmd.run(global_options).await,
             Self::Upgrade(cmd) => cmd.run(global_options).await,
         }
     }
 }
 
+/// Print where the CLI looks for its config and credentials files, and whether they exist.
+///
+/// Honors `--config-directory`.
 #[derive(Parser, Debug)]
-pub struct UpgradeCommand;
+pub struct PathCommand {}
 
-impl Command<UpgradeCommand> {
+impl Command<PathCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let mut config_dir_path = if let Some(config_dir) = &global_options.config_directory {
-            let config_dir_path = PathBuf::from(config_dir);
-
-            if config_dir_path.exists() {
-                // use this config
-                config_dir_path
-            } else {
-                panic!("The provided config directory is invalid");
-            }
-        } else if let Some(proj_dirs) = ProjectDirs::from("", "", "peridio") {
-            let cache_dir = proj_dirs.config_dir();
-
-            cache_dir.to_path_buf()
-        } else {
-            panic!("We can't determine your config path")
+        // an explicit directory is reported even when it doesn't exist
+        let config_dir_path = match &global_options.config_directory {
+            Some(config_dir) => Some(PathBuf::from(config_dir)),
+            None => Config::directory(&None)?,
         };
 
+        let config_dir_path = config_dir_path.ok_or(Error::ConfigDirectoryUnknown)?;
+
+        let describe = |path: &Path| json!({ "path": path, "exists": path.exists() });
+
+        print_json!(&json!({
+            "config_directory": describe(&config_dir_path),
+            "config": describe(&config_dir_path.join("config.json")),
+            "credentials": describe(&config_dir_path.join("credentials.json")),
+        }));
+
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+pub struct UpgradeCommand {
+    /// Print the migrated config to stdout instead of writing it to config.json.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl Command<UpgradeCommand> {
+    async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let mut config_dir_path = Config::directory(&global_options.config_directory)?
+            .ok_or(Error::ConfigDirectoryUnknown)?;
+
         config_dir_path.push("config.json");
 
-        if config_dir_path.exists() {
-            let config_file =
-                fs::read_to_string(&config_dir_path).expect("Cannot read config file");
-
-            if let Ok(config) = serde_json::from_str::<ConfigV1>(&config_file) {
-                let config_v2: Result<ConfigV2, _> = config.try_into();
-                if let Ok(configv2) = config_v2 {
-                    let file = std::fs::OpenOptions::new()
-                        .write(true)
-                        .open(&config_dir_path)
-                        .unwrap();
-                    let mut writer = BufWriter::new(file);
-                    serde_json::to_writer_pretty(&mut writer, &configv2).unwrap();
-                    writer.flush().unwrap();
-
-                    let mut msg = StyledStr::new();
-                    msg.push_str(Some(Style::Success), "success: ".to_string());
-                    msg.push_str(None, "The config file has been migrated to v2.".to_string());
-                    msg.print_success();
-                }
-            } else if serde_json::from_str::<ConfigV2>(&config_file).is_ok() {
-                eprintln!("Your config is up to date!");
-            } else {
-                panic!("Your current config file can't be upgraded automatically.");
+        let config_file = fs::read_to_string(&config_dir_path).context(ConfigReadSnafu {
+            path: &config_dir_path,
+        })?;
+
+        if let Ok(config) = serde_json::from_str::<ConfigV1>(&config_file) {
+            let configv2: ConfigV2 =
+                config
+                    .try_into()
+                    .map_err(|reason: &str| Error::ConfigUpgrade {
+                        path: config_dir_path.clone(),
+                        reason: reason.to_string(),
+                    })?;
+
+            let contents = serde_json::to_vec_pretty(&configv2).context(JsonSerializationSnafu)?;
+
+            if self.inner.dry_run {
+                println!("{}", String::from_utf8_lossy(&contents));
+                return Ok(());
             }
+
+            let backup_path = backup_config(&config_dir_path, config_file.as_bytes())?;
+            print_status!("Backed up the v1 config to {}", backup_path.display());
+
+            write_atomic(&config_dir_path, &contents).context(FileSnafu)?;
+
+            let mut msg = StyledStr::new();
+            msg.push_str(Some(Style::Success), "success: ".to_string());
+            msg.push_str(None, "The config file has been migrated to v2.".to_string());
+            msg.print_success();
         } else {
-            panic!(
-                "We can't find any config.json file in you current directory {}",
-                config_dir_path.display()
-            )
+            // not a v1 config, so it has to parse as the current one
+            serde_json::from_str::<ConfigV2>(&config_file).context(ConfigParseSnafu {
+                path: &config_dir_path,
+            })?;
+
+            print_status!("Your config is up to date!");
         }
 
         Ok(())
     }
 }
+
+/// Write `contents` to a timestamped `<config>.v1.<timestamp>.bak` next to `config_path`.
+///
+/// An existing backup is never overwritten. The backup gets the permissions of the config
+/// file, since a v1 config may hold API keys.
+fn backup_config(config_path: &Path, contents: &[u8]) -> Result<PathBuf, Error> {
+    let timestamp = OffsetDateTime::now_utc()
+        .format(format_description!(
+            "[year][month][day]T[hour][minute][second]Z"
+        ))
+        .unwrap_or_default();
+    let file_name = config_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let backup_path = config_path.with_file_name(format!("{file_name}.v1.{timestamp}.bak"));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&backup_path).context(FileSnafu)?;
+
+    if let Ok(metadata) = fs::metadata(config_path) {
+        file.set_permissions(metadata.permissions())
+            .context(FileSnafu)?;
+    }
+
+    file.write_all(contents).context(FileSnafu)?;
+
+    Ok(backup_path)
+}
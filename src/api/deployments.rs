@@ -1,6 +1,7 @@
 use super::Command;
 use crate::print_json;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use clap::Parser;
@@ -88,7 +89,10 @@ impl Command<CreateCommand> {
 
         match api.deployments().create(params).await.context(ApiSnafu)? {
             Some(deployment) => print_json!(&deployment),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "deployments create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -155,7 +159,10 @@ impl Command<GetCommand> {
 
         match api.deployments().get(params).await.context(ApiSnafu)? {
             Some(deployment) => print_json!(&deployment),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "deployments get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -184,7 +191,10 @@ impl Command<ListCommand> {
 
         match api.deployments().list(params).await.context(ApiSnafu)? {
             Some(deployments) => print_json!(&deployments),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "deployments list",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -268,7 +278,10 @@ impl Command<UpdateCommand> {
 
         match api.deployments().update(params).await.context(ApiSnafu)? {
             Some(deployment) => print_json!(&deployment),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "deployments update",
+            }
+            .fail()?,
         }
 
         Ok(())
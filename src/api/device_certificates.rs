@@ -1,6 +1,8 @@
+use super::x509;
 use super::Command;
 use crate::print_json;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use base64::{engine::general_purpose, Engine as _};
@@ -11,8 +13,10 @@ use peridio_sdk::api::device_certificates::GetDeviceCertificateParams;
 use peridio_sdk::api::device_certificates::ListDeviceCertificateParams;
 use peridio_sdk::api::Api;
 use peridio_sdk::api::ApiOptions;
+use serde_json::json;
 use snafu::ResultExt;
 use std::fs;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 pub enum DeviceCertificatesCommand {
@@ -46,34 +50,77 @@ pub struct CreateCommand {
     /// The certificate PEM content.
     #[arg(
         long,
-        conflicts_with("certificate_path"),
-        required_unless_present("certificate_path")
+        conflicts_with_all(["certificate_path", "signer"]),
+        required_unless_present_any(["certificate_path", "signer"])
     )]
     certificate: Option<String>,
 
     /// The path to the certificate's PEM content
     #[arg(
         long,
-        conflicts_with("certificate"),
-        required_unless_present("certificate")
+        conflicts_with_all(["certificate", "signer"]),
+        required_unless_present_any(["certificate", "signer"])
     )]
     certificate_path: Option<String>,
+
+    /// Generate the certificate instead, signed by this certificate authority in your Peridio CLI config. The device identifier is used as its Common Name (CN).
+    #[arg(long, requires_all(["start_date", "end_date"]))]
+    signer: Option<String>,
+
+    /// The start date of the generated certificate's validity period (format: YYYY-MM-DD or an RFC 3339 datetime such as 2025-12-31T23:59:59Z)
+    #[arg(long, requires("signer"))]
+    start_date: Option<String>,
+
+    /// The end date of the generated certificate's validity period (format: YYYY-MM-DD or an RFC 3339 datetime such as 2025-12-31T23:59:59Z)
+    #[arg(long, requires("signer"))]
+    end_date: Option<String>,
+
+    /// Directory to save the generated certificate and private key to (defaults to current working directory)
+    #[arg(long, requires("signer"))]
+    out: Option<PathBuf>,
 }
 
 impl Command<CreateCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let certificate = if let Some(cert_path) = self.inner.certificate_path {
+        let generated = if let Some(signer) = &self.inner.signer {
+            let (signer_key, signer_cert) = x509::config_signer(&global_options, signer)?;
+
+            Some(x509::device_certificate(
+                &self.inner.device_identifier,
+                x509::parse_date(self.inner.start_date.as_deref().unwrap())?,
+                x509::parse_date(self.inner.end_date.as_deref().unwrap())?,
+                &signer_key,
+                &signer_cert,
+            )?)
+        } else {
+            None
+        };
+
+        let certificate = if let Some((cert, _)) = &generated {
+            cert.pem()
+        } else if let Some(cert_path) = self.inner.certificate_path {
             fs::read_to_string(cert_path).unwrap()
         } else {
             self.inner.certificate.unwrap()
         };
 
+        // write the files first, so a bad --out fails before the certificate is registered
+        let written = match &generated {
+            Some((cert, key_pair)) => Some(x509::write_certificate_and_key(
+                self.inner.out,
+                &self.inner.device_identifier,
+                cert,
+                key_pair,
+            )?),
+            None => None,
+        };
+
         let encoded_certificate = general_purpose::STANDARD.encode(&certificate);
 
         let params = CreateDeviceCertificateParams {
             organization_name: global_options.organization_name.unwrap(),
             product_name: self.inner.product_name,
-            device_identifier: self.inner.device_identifier,
+            device_identifier: self.inner.device_identifier.clone(),
             cert: encoded_certificate,
         };
 
@@ -89,8 +136,21 @@ impl Command<CreateCommand> {
             .await
             .context(ApiSnafu)?
         {
-            Some(device_certificate) => print_json!(&device_certificate),
-            None => panic!(),
+            Some(device_certificate) => {
+                if let Some((certificate, private_key)) = written {
+                    print_json!(&json!({
+                        "device_certificate": device_certificate,
+                        "certificate": certificate,
+                        "private_key": private_key
+                    }));
+                } else {
+                    print_json!(&device_certificate);
+                }
+            }
+            None => EmptyResponseSnafu {
+                context: "device-certificates create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -178,7 +238,10 @@ impl Command<GetCommand> {
             .context(ApiSnafu)?
         {
             Some(device_certificate) => print_json!(&device_certificate),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "device-certificates get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -217,7 +280,10 @@ impl Command<ListCommand> {
             .context(ApiSnafu)?
         {
             Some(device_certificate) => print_json!(&device_certificate),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "device-certificates list",
+            }
+            .fail()?,
         }
 
         Ok(())
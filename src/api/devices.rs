@@ -1,29 +1,44 @@
+use std::collections::HashMap;
 use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
 
+use super::x509;
 use super::Command;
 use crate::print_json;
+use crate::utils::retry::{is_rate_limited, retry_with_backoff, DEFAULT_BASE_DELAY};
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
 use crate::ApiSnafu;
+use crate::CsvParseSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
+use crate::JsonSerializationSnafu;
 use base64::{engine::general_purpose, Engine as _};
 use clap::Parser;
+use futures_util::stream;
+use futures_util::StreamExt;
+use peridio_sdk::api::device_certificates::CreateDeviceCertificateParams;
 use peridio_sdk::api::devices::{
     AuthenticateDeviceParams, CreateDeviceParams, DeleteDeviceParams, GetDeviceParams,
     ListDeviceParams, UpdateDeviceParams,
 };
 use peridio_sdk::api::Api;
 use peridio_sdk::api::ApiOptions;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
 use snafu::ResultExt;
 
 #[derive(Parser, Debug)]
 pub enum DevicesCommand {
     Authenticate(Command<AuthenticateCommand>),
+    BulkCreate(Command<BulkCreateCommand>),
     Create(Command<CreateCommand>),
     Delete(Command<DeleteCommand>),
     Get(Command<GetCommand>),
     List(Command<ListCommand>),
+    Provision(Command<ProvisionCommand>),
     Update(Command<UpdateCommand>),
 }
 
@@ -31,10 +46,12 @@ impl DevicesCommand {
     pub async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
         match self {
             Self::Authenticate(cmd) => cmd.run(global_options).await,
+            Self::BulkCreate(cmd) => cmd.run(global_options).await,
             Self::Create(cmd) => cmd.run(global_options).await,
             Self::Delete(cmd) => cmd.run(global_options).await,
             Self::Get(cmd) => cmd.run(global_options).await,
             Self::List(cmd) => cmd.run(global_options).await,
+            Self::Provision(cmd) => cmd.run(global_options).await,
             Self::Update(cmd) => cmd.run(global_options).await,
         }
     }
@@ -104,13 +121,176 @@ impl Command<CreateCommand> {
 
         match api.devices().create(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "devices create",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
 
+/// Create many devices at once from a CSV file.
+///
+/// The file must start with a header row. The `identifier` column is required, the `description`, `tags`, `target` and `cohort_prn` columns are optional. Multiple tags are separated by commas within their (quoted) cell.
+///
+/// A summary of the created and failed devices, keyed by identifier, is printed at the end. The command exits with an error if any device could not be created.
+#[derive(Parser, Debug)]
+pub struct BulkCreateCommand {
+    /// The path to the CSV file describing the devices to create.
+    #[arg(long)]
+    file: PathBuf,
+
+    /// The name of the product you wish to create the devices within.
+    #[arg(long)]
+    product_name: String,
+
+    /// Limit the number of devices created at the same time.
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(1..))]
+    concurrency: u8,
+
+    /// The number of times to retry creating a device that was rate limited, backing off exponentially between attempts.
+    #[arg(long, default_value_t = 5)]
+    max_retries: u8,
+}
+
+#[derive(Deserialize, Debug)]
+struct DeviceRow {
+    identifier: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    tags: Option<String>,
+    #[serde(default)]
+    target: Option<String>,
+    #[serde(default)]
+    cohort_prn: Option<String>,
+}
+
+impl Command<BulkCreateCommand> {
+    async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let rows = csv::Reader::from_path(&self.inner.file)
+            .and_then(|mut reader| reader.deserialize().collect::<Result<Vec<DeviceRow>, _>>())
+            .context(CsvParseSnafu {
+                path: &self.inner.file,
+            })?;
+
+        // results are keyed by identifier, so a repeated one would hide a row
+        if let Some((identifier, row, first_row)) = duplicate_identifier(&rows) {
+            return Err(Error::DuplicateDeviceIdentifier {
+                path: self.inner.file,
+                identifier: identifier.to_string(),
+                row,
+                first_row,
+            });
+        }
+
+        let api = Api::new(ApiOptions {
+            api_key: global_options.api_key.unwrap(),
+            endpoint: global_options.base_url,
+            ca_bundle_path: global_options.ca_path,
+        });
+
+        let organization_name = Arc::new(global_options.organization_name.unwrap());
+        let product_name = Arc::new(self.inner.product_name);
+        let max_retries = self.inner.max_retries.into();
+        let total = rows.len();
+
+        let results = stream::iter(rows)
+            .map(|row| {
+                let api = api.clone();
+                let organization_name = Arc::clone(&organization_name);
+                let product_name = Arc::clone(&product_name);
+
+                async move {
+                    let tags = row.tags.as_ref().map(|tags| {
+                        tags.split(',')
+                            .map(|tag| tag.trim().to_string())
+                            .filter(|tag| !tag.is_empty())
+                            .collect()
+                    });
+
+                    let result = retry_with_backoff(
+                        || async {
+                            let params = CreateDeviceParams {
+                                organization_name: organization_name.to_string(),
+                                product_name: product_name.to_string(),
+                                description: row.description.clone(),
+                                healthy: None,
+                                identifier: row.identifier.clone(),
+                                last_communication: None,
+                                tags: tags.clone(),
+                                target: row.target.clone(),
+                                cohort_prn: row.cohort_prn.clone(),
+                            };
+
+                            match api.devices().create(params).await.context(ApiSnafu)? {
+                                Some(device) => {
+                                    serde_json::to_value(device).context(JsonSerializationSnafu)
+                                }
+                                None => EmptyResponseSnafu {
+                                    context: "devices create",
+                                }
+                                .fail(),
+                            }
+                        },
+                        max_retries,
+                        DEFAULT_BASE_DELAY,
+                        is_rate_limited,
+                    )
+                    .await;
+
+                    (row.identifier, result)
+                }
+            })
+            .buffer_unordered(self.inner.concurrency.into())
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut created = Map::new();
+        let mut failed = Map::new();
+
+        for (identifier, result) in results {
+            match result {
+                Ok(device) => {
+                    created.insert(identifier, device);
+                }
+                Err(error) => {
+                    failed.insert(identifier, Value::String(error.to_string()));
+                }
+            }
+        }
+
+        print_json!(&json!({
+            "created": created,
+            "failed": failed,
+        }));
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::BulkCreate {
+                failed: failed.len(),
+                total,
+            })
+        }
+    }
+}
+
+/// Find the first identifier that appears in more than one of `rows`, with the CSV row numbers
+/// of its repeat and its first occurrence. Row 1 is the header.
+fn duplicate_identifier(rows: &[DeviceRow]) -> Option<(&str, usize, usize)> {
+    let mut seen = HashMap::new();
+
+    rows.iter().enumerate().find_map(|(index, row)| {
+        let row_number = index + 2;
+
+        seen.insert(row.identifier.as_str(), row_number)
+            .map(|first_row| (row.identifier.as_str(), row_number, first_row))
+    })
+}
+
 #[derive(Parser, Debug)]
 pub struct DeleteCommand {
     /// The identifier of the device you wish to delete.
@@ -171,7 +351,10 @@ impl Command<GetCommand> {
 
         match api.devices().get(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "devices get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -200,7 +383,10 @@ impl Command<ListCommand> {
 
         match api.devices().list(params).await.context(ApiSnafu)? {
             Some(devices) => print_json!(&devices),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "devices list",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -259,7 +445,10 @@ impl Command<UpdateCommand> {
 
         match api.devices().update(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "devices update",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -312,9 +501,125 @@ impl Command<AuthenticateCommand> {
 
         match api.devices().authenticate(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "devices authenticate",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
+
+/// Generate a client certificate for a device, signed by a certificate authority in your Peridio CLI config, and register it with the device.
+///
+/// The certificate and its private key are written to `--out` as `{device-identifier}-certificate.pem` and `{device-identifier}-private-key.pem`.
+///
+/// A new key pair is generated on every run, so the certificate can never share a fingerprint with one the device already has.
+#[derive(Parser, Debug)]
+pub struct ProvisionCommand {
+    /// The identifier of the device to provision. Also used as the certificate's Common Name (CN).
+    #[arg(long)]
+    device_identifier: String,
+
+    /// The name of the product the device belongs to.
+    #[arg(long)]
+    product_name: String,
+
+    /// The name of a certificate authority in your Peridio CLI config to sign the certificate with.
+    #[arg(long)]
+    signer: String,
+
+    /// The start date of the certificate's validity period (format: YYYY-MM-DD or an RFC 3339 datetime such as 2025-12-31T23:59:59Z)
+    #[arg(long)]
+    start_date: String,
+
+    /// The end date of the certificate's validity period (format: YYYY-MM-DD or an RFC 3339 datetime such as 2025-12-31T23:59:59Z)
+    #[arg(long)]
+    end_date: String,
+
+    /// Directory to save the created certificate and private key to (defaults to current working directory)
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+impl Command<ProvisionCommand> {
+    async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let (signer_key, signer_cert) = x509::config_signer(&global_options, &self.inner.signer)?;
+        let (cert, key_pair) = x509::device_certificate(
+            &self.inner.device_identifier,
+            x509::parse_date(&self.inner.start_date)?,
+            x509::parse_date(&self.inner.end_date)?,
+            &signer_key,
+            &signer_cert,
+        )?;
+
+        let organization_name = global_options.organization_name.unwrap();
+
+        let api = Api::new(ApiOptions {
+            api_key: global_options.api_key.unwrap(),
+            endpoint: global_options.base_url,
+            ca_bundle_path: global_options.ca_path,
+        });
+
+        // write the files first, so a bad --out fails before the certificate is registered
+        let (certificate, private_key) = x509::write_certificate_and_key(
+            self.inner.out,
+            &self.inner.device_identifier,
+            &cert,
+            &key_pair,
+        )?;
+
+        let params = CreateDeviceCertificateParams {
+            organization_name,
+            product_name: self.inner.product_name,
+            device_identifier: self.inner.device_identifier.clone(),
+            cert: general_purpose::STANDARD.encode(cert.pem()),
+        };
+
+        let device_certificate = match api
+            .device_certificates()
+            .create(params)
+            .await
+            .context(ApiSnafu)?
+        {
+            Some(device_certificate) => device_certificate,
+            None => EmptyResponseSnafu {
+                context: "devices provision",
+            }
+            .fail()?,
+        };
+
+        print_json!(&json!({
+            "device_certificate": device_certificate,
+            "certificate": certificate,
+            "private_key": private_key
+        }));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(identifier: &str) -> DeviceRow {
+        DeviceRow {
+            identifier: identifier.to_string(),
+            description: None,
+            tags: None,
+            target: None,
+            cohort_prn: None,
+        }
+    }
+
+    #[test]
+    fn duplicate_identifier_reports_csv_row_numbers() {
+        assert_eq!(duplicate_identifier(&[row("a"), row("b")]), None);
+        assert_eq!(
+            duplicate_identifier(&[row("a"), row("b"), row("c"), row("b")]),
+            Some(("b", 5, 3))
+        );
+    }
+}
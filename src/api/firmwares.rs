@@ -1,6 +1,7 @@
 use super::Command;
 use crate::print_json;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use clap::Parser;
@@ -65,7 +66,10 @@ impl Command<CreateCommand> {
 
         match api.firmwares().create(params).await.context(ApiSnafu)? {
             Some(firmware) => print_json!(&firmware),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "firmwares create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -132,7 +136,10 @@ impl Command<GetCommand> {
 
         match api.firmwares().get(params).await.context(ApiSnafu)? {
             Some(firmware) => print_json!(&firmware),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "firmwares get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -161,7 +168,10 @@ impl Command<ListCommand> {
 
         match api.firmwares().list(params).await.context(ApiSnafu)? {
             Some(firmwares) => print_json!(&firmwares),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "firmwares list",
+            }
+            .fail()?,
         }
 
         Ok(())
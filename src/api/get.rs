@@ -0,0 +1,61 @@
+use clap::Args;
+use serde::Serialize;
+use serde_json::Value;
+use snafu::ResultExt;
+
+use crate::print_json;
+use crate::Error;
+use crate::JsonSerializationSnafu;
+
+#[derive(Args, Debug, Default)]
+pub struct GetArgs {
+    /// Only print these top-level fields of the resource. Separate multiple fields with commas.
+    #[arg(long, value_delimiter = ',')]
+    pub fields: Option<Vec<String>>,
+}
+
+impl GetArgs {
+    /// Prints `response`, keeping only the fields given with `--fields` when set.
+    pub fn print<T: Serialize>(&self, response: &T) -> Result<(), Error> {
+        let Some(fields) = &self.fields else {
+            print_json!(response);
+
+            return Ok(());
+        };
+
+        let mut response = serde_json::to_value(response).context(JsonSerializationSnafu)?;
+
+        // get responses carry the resource in their only field, e.g. `binary`
+        let resource = match &mut response {
+            Value::Object(map) if map.len() == 1 && map.values().all(Value::is_object) => {
+                map.values_mut().next().unwrap()
+            }
+            resource => resource,
+        };
+
+        if let Value::Object(resource) = resource {
+            let unknown: Vec<&str> = fields
+                .iter()
+                .filter(|field| !resource.contains_key(*field))
+                .map(String::as_str)
+                .collect();
+
+            if !unknown.is_empty() {
+                return Err(Error::UnknownFields {
+                    unknown: unknown.join(", "),
+                    available: resource
+                        .keys()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                });
+            }
+
+            resource.retain(|key, _| fields.contains(key));
+        }
+
+        print_json!(&response);
+
+        Ok(())
+    }
+}
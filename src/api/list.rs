@@ -1,4 +1,19 @@
+use std::future::Future;
+
 use clap::Args;
+use serde::Serialize;
+use serde_json::{json, Value};
+use snafu::ResultExt;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::print_json;
+use crate::print_status;
+use crate::utils::{self, OutputFormat};
+use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
+use crate::Error;
+use crate::JsonSerializationSnafu;
 
 #[derive(Args, Debug)]
 pub struct ListArgs {
@@ -9,9 +24,204 @@ pub struct ListArgs {
     #[arg(long, value_enum)]
     pub order: Option<String>,
     /// A search query per the Peridio API's search query language. It is recommended to quote the value of this option.
-    #[arg(long)]
-    pub search: String,
+    #[arg(long, required_unless_present_any = ["since", "until"])]
+    pub search: Option<String>,
     /// A cursor for pagination across multiple pages of results. Don't include this parameter on the first call. Use the next_page value returned in a previous response (if not null) to request subsequent results.
     #[arg(long)]
     pub page: Option<String>,
+    /// Follow the next_page cursor and print all results as a single JSON array.
+    ///
+    /// With `--output jsonl`, results are printed an item per line as each page arrives instead.
+    #[arg(long, conflicts_with = "page")]
+    pub all: bool,
+    /// Follow the next_page cursor like --all, but only print how many results there are.
+    ///
+    /// Prints a bare number, or `{"count": N}` when --output is given. Fails when there are more
+    /// results than --max-pages pages hold.
+    #[arg(long, conflicts_with_all = ["page", "all"])]
+    pub count: bool,
+    /// The maximum number of pages to fetch when using --all or --count.
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_pages: u32,
+    /// Only include resources inserted at or after this RFC 3339 timestamp, e.g. 2024-01-31T00:00:00Z.
+    #[arg(long, value_parser = parse_timestamp)]
+    pub since: Option<OffsetDateTime>,
+    /// Only include resources inserted before this RFC 3339 timestamp, e.g. 2024-02-01T00:00:00Z.
+    #[arg(long, value_parser = parse_timestamp)]
+    pub until: Option<OffsetDateTime>,
+}
+
+impl ListArgs {
+    /// Builds the search query from `--search`, the `(field, value)` filters that were
+    /// given and the `--since`/`--until` range on `inserted_at`, joined with `and`.
+    pub fn search_query(&self, filters: &[(&str, Option<&str>)]) -> String {
+        let time_range = [(":>=", self.since), (":<", self.until)]
+            .into_iter()
+            .filter_map(|(operator, timestamp)| {
+                // formatting a parsed RFC 3339 timestamp back to RFC 3339 can't fail
+                timestamp
+                    .and_then(|timestamp| timestamp.format(&Rfc3339).ok())
+                    .map(|timestamp| format!("inserted_at{operator}'{timestamp}'"))
+            });
+
+        self.search
+            .iter()
+            .cloned()
+            .chain(
+                filters
+                    .iter()
+                    .filter_map(|(field, value)| value.map(|value| format!("{field}:'{value}'"))),
+            )
+            .chain(time_range)
+            .collect::<Vec<_>>()
+            .join(" and ")
+    }
+
+    /// Fetches and prints one page of results, every page when `--all` is set, or the number
+    /// of results when `--count` is set.
+    ///
+    /// `fetch` is called with the page cursor to request, `context` names the command in errors.
+    pub async fn print_pages<T, F, Fut>(&self, context: &str, mut fetch: F) -> Result<(), Error>
+    where
+        T: Serialize,
+        F: FnMut(Option<String>) -> Fut,
+        Fut: Future<Output = Result<Option<T>, peridio_sdk::api::Error>>,
+    {
+        if !self.all && !self.count {
+            match fetch(self.page.clone()).await.context(ApiSnafu)? {
+                Some(response) => print_json!(&response),
+                None => EmptyResponseSnafu { context }.fail()?,
+            }
+
+            return Ok(());
+        }
+
+        let complete = if self.count {
+            let mut count = 0;
+
+            let complete = visit_pages(context, self.max_pages, fetch, |items| {
+                count += items.len();
+                Ok(())
+            })
+            .await?;
+
+            // a truncated count would be mistaken for the real one, so it's never printed
+            if !complete {
+                return Err(Error::IncompleteCount {
+                    count,
+                    max_pages: self.max_pages,
+                });
+            }
+
+            if utils::output_format_given() {
+                print_json!(&json!({ "count": count }));
+            } else {
+                println!("{count}");
+            }
+
+            complete
+        } else if utils::output_format() == OutputFormat::Jsonl {
+            // print each page as it arrives rather than holding every page in memory
+            visit_pages(context, self.max_pages, fetch, |items| {
+                utils::print_output(&items)
+            })
+            .await?
+        } else {
+            let (items, complete) = fetch_pages(context, self.max_pages, fetch).await?;
+
+            print_json!(&items);
+
+            complete
+        };
+
+        if !complete {
+            print_status!(
+                "Stopped after {} pages, more results are available. Use --max-pages to fetch more.",
+                self.max_pages
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_timestamp(value: &str) -> Result<OffsetDateTime, String> {
+    OffsetDateTime::parse(value, &Rfc3339).map_err(|error| {
+        format!("expected an RFC 3339 timestamp, e.g. 2024-01-31T00:00:00Z: {error}")
+    })
+}
+
+/// Follows the `next_page` cursor for up to `max_pages` pages and collects the items of
+/// every page.
+///
+/// Returns the items and whether the last page was reached.
+pub async fn fetch_pages<T, F, Fut>(
+    context: &str,
+    max_pages: u32,
+    fetch: F,
+) -> Result<(Vec<Value>, bool), Error>
+where
+    T: Serialize,
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<Option<T>, peridio_sdk::api::Error>>,
+{
+    let mut items: Vec<Value> = Vec::new();
+
+    let complete = visit_pages(context, max_pages, fetch, |page_items| {
+        items.extend(page_items);
+        Ok(())
+    })
+    .await?;
+
+    Ok((items, complete))
+}
+
+/// Follows the `next_page` cursor for up to `max_pages` pages, handing the items of each
+/// page to `visit` as it arrives.
+///
+/// Returns whether the last page was reached.
+async fn visit_pages<T, F, Fut, V>(
+    context: &str,
+    max_pages: u32,
+    mut fetch: F,
+    mut visit: V,
+) -> Result<bool, Error>
+where
+    T: Serialize,
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<Option<T>, peridio_sdk::api::Error>>,
+    V: FnMut(Vec<Value>) -> Result<(), Error>,
+{
+    let mut page = None;
+
+    for _ in 0..max_pages {
+        let response = match fetch(page.take()).await.context(ApiSnafu)? {
+            Some(response) => response,
+            None => EmptyResponseSnafu { context }.fail()?,
+        };
+
+        let response = serde_json::to_value(&response).context(JsonSerializationSnafu)?;
+
+        page = response
+            .get("next_page")
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+
+        // list responses carry their results in the only array field, e.g. `binaries`
+        if let Value::Object(map) = response {
+            if let Some(Value::Array(page_items)) = map
+                .into_iter()
+                .map(|(_, value)| value)
+                .find(Value::is_array)
+            {
+                visit(page_items)?;
+            }
+        }
+
+        if page.is_none() {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
 }
@@ -6,13 +6,16 @@ mod binary_signatures;
 mod bundles;
 mod ca_certificates;
 mod cohorts;
+mod completions;
 mod config;
 mod deployments;
 mod device_certificates;
 mod devices;
 mod firmwares;
+mod get;
 mod list;
 mod organization;
+mod ping;
 mod products;
 mod products_v2;
 mod releases;
@@ -20,6 +23,7 @@ mod signing_keys;
 mod tunnels;
 mod upgrade;
 mod users;
+mod version;
 mod webhooks;
 mod x509;
 use crate::utils::Style;
@@ -52,6 +56,15 @@ pub enum CliCommands {
     /// Create X.509 certificates and private keys
     #[command(subcommand)]
     X509(x509::X509Command),
+    /// Generate shell completion scripts
+    #[command()]
+    Completions(completions::CompletionsCommand),
+    /// Check connectivity to the Peridio API
+    #[command()]
+    Ping(ping::PingCommand),
+    /// Print build information
+    #[command()]
+    Version(version::VersionCommand),
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -100,19 +113,38 @@ impl CliCommands {
     pub(crate) async fn run(self, global_options: GlobalOptions) -> Result<(), crate::Error> {
         match self {
             CliCommands::ApiCommand(api) => {
-                // require api key
-                let mut missing_arguments = Vec::new();
+                // adding a signing key pair to the config never calls the API
+                let local_only = matches!(
+                    api,
+                    ApiCommand::SigningKeys(signing_keys::SigningKeysCommand::AddToConfig(_))
+                );
 
-                if global_options.api_key.is_none() {
-                    missing_arguments.push("--api-key".to_owned());
-                }
+                if !local_only {
+                    // require api key
+                    let mut missing_arguments = Vec::new();
 
-                // require organization name
-                if global_options.organization_name.is_none() {
-                    missing_arguments.push("--organization-name".to_owned());
-                }
+                    if global_options.api_key.is_none() {
+                        missing_arguments.push("--api-key".to_owned());
+                    }
 
-                Self::print_missing_arguments(missing_arguments);
+                    // require organization name
+                    if global_options.organization_name.is_none() {
+                        missing_arguments.push("--organization-name".to_owned());
+                    }
+
+                    // credentials are only read from a config directory through a profile
+                    if !missing_arguments.is_empty()
+                        && global_options.config_directory.is_some()
+                        && global_options.profile.is_none()
+                    {
+                        missing_arguments.push("--profile".to_owned());
+                    }
+
+                    Self::print_missing_arguments(
+                        missing_arguments,
+                        global_options.loaded_profile.as_deref(),
+                    );
+                }
 
                 match api {
                     ApiCommand::Artifacts(cmd) => cmd.run(global_options).await?,
@@ -140,12 +172,20 @@ impl CliCommands {
             CliCommands::Upgrade(cmd) => cmd.run().await?,
             CliCommands::Config(cmd) => cmd.run(global_options).await?,
             CliCommands::X509(cmd) => cmd.run(global_options).await?,
+            CliCommands::Completions(cmd) => cmd.run()?,
+            CliCommands::Ping(cmd) => cmd.run(global_options).await?,
+            CliCommands::Version(cmd) => cmd.run()?,
         };
 
         Ok(())
     }
 
-    pub(crate) fn print_missing_arguments(missing_arguments: Vec<String>) {
+    /// Exits with an error listing `missing_arguments`, if there are any. `loaded_profile` is the
+    /// profile that was read, so users can tell an incomplete profile from an unused one.
+    pub(crate) fn print_missing_arguments(
+        missing_arguments: Vec<String>,
+        loaded_profile: Option<&str>,
+    ) {
         if !missing_arguments.is_empty() {
             let mut error = StyledStr::new();
 
@@ -157,6 +197,18 @@ impl CliCommands {
             for missing_argument in missing_arguments.iter() {
                 error.push_str(Some(Style::Success), format!("\t{missing_argument}\r\n"));
             }
+            match loaded_profile {
+                Some(profile_name) => {
+                    error.push_str(None, "\r\nProfile '".to_string());
+                    error.push_str(Some(Style::Warning), profile_name.to_string());
+                    error.push_str(
+                        None,
+                        "' was loaded but does not provide these. Its API key is read from credentials.json.\r\n"
+                            .to_string(),
+                    );
+                }
+                None => error.push_str(None, "\r\nNo profile was loaded.\r\n".to_string()),
+            }
             error.push_str(None, "\r\nThey must be supplied either:\r\n".to_string());
             error.push_str(
                 None,
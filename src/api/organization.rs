@@ -1,6 +1,7 @@
 use super::Command;
 use crate::print_json;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use clap::Parser;
@@ -65,7 +66,10 @@ impl Command<AddUserCommand> {
             .context(ApiSnafu)?
         {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "organizations add-user",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -133,7 +137,10 @@ impl Command<GetUserCommand> {
             .context(ApiSnafu)?
         {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "organizations get-user",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -162,7 +169,10 @@ impl Command<ListUsersCommand> {
             .context(ApiSnafu)?
         {
             Some(devices) => print_json!(&devices),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "organizations list-users",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -201,7 +211,10 @@ impl Command<UpdateUserCommand> {
             .context(ApiSnafu)?
         {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "organizations update-user",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -0,0 +1,88 @@
+use clap::Parser;
+use peridio_sdk::api::Api;
+use peridio_sdk::api::ApiOptions;
+
+use crate::api::CliCommands;
+use crate::print_json;
+use crate::utils::ApiFailure;
+use crate::utils::Style;
+use crate::utils::StyledStr;
+use crate::EmptyResponseSnafu;
+use crate::Error;
+use crate::GlobalOptions;
+
+/// Check connectivity to the Peridio API and that the API key is accepted.
+///
+/// Exits with 69 when the API can't be reached, 76 when the TLS handshake fails and 77 when the API key is rejected.
+#[derive(Parser, Debug)]
+pub struct PingCommand {}
+
+impl PingCommand {
+    pub async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        // require api key
+        let mut missing_arguments = Vec::new();
+
+        if global_options.api_key.is_none() {
+            missing_arguments.push("--api-key".to_owned());
+        }
+
+        CliCommands::print_missing_arguments(
+            missing_arguments,
+            global_options.loaded_profile.as_deref(),
+        );
+
+        let endpoint = global_options
+            .base_url
+            .clone()
+            .unwrap_or_else(|| "the Peridio API".to_string());
+
+        let api = Api::new(ApiOptions {
+            api_key: global_options.api_key.unwrap(),
+            endpoint: global_options.base_url,
+            ca_bundle_path: global_options.ca_path,
+        });
+
+        let source = match api.users().me().await {
+            Ok(Some(users_me)) => {
+                print_json!(&serde_json::json!({
+                    "status": "ok",
+                    "user": users_me,
+                }));
+
+                return Ok(());
+            }
+            Ok(None) => EmptyResponseSnafu { context: "ping" }.fail()?,
+            Err(source) => source,
+        };
+
+        let (message, hint, code) = match ApiFailure::of(&source) {
+            ApiFailure::Network => (
+                format!("Unable to reach {endpoint}.\r\n"),
+                "\tCheck --base-url and your network connection.",
+                // UNAVAILABLE
+                69,
+            ),
+            ApiFailure::Tls => (
+                format!("TLS handshake with {endpoint} failed.\r\n"),
+                "\tCheck the CA bundle given by --ca-path or PERIDIO_CA_PATH.",
+                // PROTOCOL
+                76,
+            ),
+            ApiFailure::Unauthorized => (
+                format!("{endpoint} rejected the API key.\r\n"),
+                "\tCheck --api-key, PERIDIO_API_KEY or the credentials of your profile.",
+                // NOPERM
+                77,
+            ),
+            ApiFailure::Other => return Err(Error::Api { source }),
+        };
+
+        let mut error = StyledStr::new();
+        error.push_str(Some(Style::Error), "error: ".to_string());
+        error.push_str(None, message);
+        error.push_str(Some(Style::Warning), hint.to_string());
+        let _ = error.print_err();
+
+        std::process::exit(code)
+    }
+}
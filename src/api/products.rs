@@ -1,6 +1,7 @@
 use super::Command;
 use crate::print_json;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use clap::Parser;
@@ -70,7 +71,10 @@ impl Command<CreateCommand> {
 
         match api.products().create(params).await.context(ApiSnafu)? {
             Some(product) => print_json!(&product),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "products create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -127,7 +131,10 @@ impl Command<GetCommand> {
 
         match api.products().get(params).await.context(ApiSnafu)? {
             Some(product) => print_json!(&product),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "products get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -151,7 +158,10 @@ impl Command<ListCommand> {
 
         match api.products().list(params).await.context(ApiSnafu)? {
             Some(product) => print_json!(&product),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "products list",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -187,7 +197,10 @@ impl Command<UpdateCommand> {
 
         match api.products().update(params).await.context(ApiSnafu)? {
             Some(product) => print_json!(&product),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "products update",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -226,7 +239,10 @@ impl Command<AddUserCommand> {
 
         match api.product_users().add(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "products add-user",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -293,7 +309,10 @@ impl Command<GetUserCommand> {
 
         match api.product_users().get(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "products get-user",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -322,7 +341,10 @@ impl Command<ListUsersCommand> {
 
         match api.product_users().list(params).await.context(ApiSnafu)? {
             Some(devices) => print_json!(&devices),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "products list-users",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -361,7 +383,10 @@ impl Command<UpdateUserCommand> {
 
         match api.product_users().update(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "products update-user",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -1,18 +1,30 @@
 use super::Command;
+use crate::api::get::GetArgs;
+use crate::api::list::fetch_pages;
 use crate::api::list::ListArgs;
 use crate::print_json;
+use crate::print_status;
+use crate::utils::retry::{
+    is_api_rate_limited, is_rate_limited, retry_with_backoff, DEFAULT_BASE_DELAY,
+};
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
+use crate::JsonSerializationSnafu;
+use crate::MissingFieldSnafu;
 use clap::Parser;
+use peridio_sdk::api::cohorts::ListCohortsParams;
+use peridio_sdk::api::devices::ListDeviceParams;
 use peridio_sdk::api::products_v2::CreateProductV2Params;
 use peridio_sdk::api::products_v2::GetProductV2Params;
 use peridio_sdk::api::products_v2::ListProductsV2Params;
 use peridio_sdk::api::products_v2::UpdateProductV2Params;
 use peridio_sdk::api::Api;
 use peridio_sdk::api::ApiOptions;
+use serde_json::json;
 use snafu::ResultExt;
 
 #[derive(Parser, Debug)]
@@ -21,6 +33,7 @@ pub enum ProductsV2Command {
     List(Command<ListCommand>),
     Get(Command<GetCommand>),
     Update(Command<UpdateCommand>),
+    Summary(Command<SummaryCommand>),
 }
 
 impl ProductsV2Command {
@@ -30,6 +43,7 @@ impl ProductsV2Command {
             Self::List(cmd) => cmd.run(global_options).await,
             Self::Get(cmd) => cmd.run(global_options).await,
             Self::Update(cmd) => cmd.run(global_options).await,
+            Self::Summary(cmd) => cmd.run(global_options).await,
         }
     }
 }
@@ -69,7 +83,10 @@ impl Command<CreateCommand> {
 
         match api.products_v2().create(params).await.context(ApiSnafu)? {
             Some(product) => print_json!(&product),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "products-v2 create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -84,25 +101,27 @@ pub struct ListCommand {
 
 impl Command<ListCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let params = ListProductsV2Params {
-            limit: self.inner.list_args.limit,
-            order: self.inner.list_args.order,
-            search: self.inner.list_args.search,
-            page: self.inner.list_args.page,
-        };
-
         let api = Api::new(ApiOptions {
             api_key: global_options.api_key.unwrap(),
             endpoint: global_options.base_url,
             ca_bundle_path: global_options.ca_path,
         });
 
-        match api.products_v2().list(params).await.context(ApiSnafu)? {
-            Some(product) => print_json!(&product),
-            None => panic!(),
-        }
+        let list_args = &self.inner.list_args;
 
-        Ok(())
+        list_args
+            .print_pages("products-v2 list", |page| {
+                let params = ListProductsV2Params {
+                    limit: list_args.limit,
+                    order: list_args.order.clone(),
+                    search: list_args.search_query(&[]),
+                    page,
+                };
+                let api = api.clone();
+
+                async move { api.products_v2().list(params).await }
+            })
+            .await
     }
 }
 
@@ -114,6 +133,9 @@ pub struct GetCommand {
         value_parser = PRNValueParser::new(PRNType::Product)
     )]
     prn: String,
+
+    #[clap(flatten)]
+    get_args: GetArgs,
 }
 
 impl Command<GetCommand> {
@@ -129,8 +151,11 @@ impl Command<GetCommand> {
         });
 
         match api.products_v2().get(params).await.context(ApiSnafu)? {
-            Some(product) => print_json!(&product),
-            None => panic!(),
+            Some(product) => self.inner.get_args.print(&product)?,
+            None => EmptyResponseSnafu {
+                context: "products-v2 get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -169,9 +194,144 @@ impl Command<UpdateCommand> {
 
         match api.products_v2().update(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "products-v2 update",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
+
+/// Print how many cohorts and devices a product has.
+///
+/// Cohorts are counted by following every page of cohorts list, devices by listing the product's devices. Both are fetched concurrently.
+#[derive(Parser, Debug)]
+pub struct SummaryCommand {
+    /// The PRN of the product to summarize.
+    #[arg(
+        long,
+        value_parser = PRNValueParser::new(PRNType::Product)
+    )]
+    prn: String,
+
+    /// The maximum number of pages of cohorts to fetch.
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+    max_pages: u32,
+
+    /// The number of times to retry an API request that was rate limited, backing off exponentially between attempts.
+    #[arg(long, default_value_t = 5)]
+    max_retries: u8,
+}
+
+impl Command<SummaryCommand> {
+    async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let api = Api::new(ApiOptions {
+            api_key: global_options.api_key.unwrap(),
+            endpoint: global_options.base_url,
+            ca_bundle_path: global_options.ca_path,
+        });
+
+        let max_retries = self.inner.max_retries.into();
+
+        let product = match retry_with_backoff(
+            || async {
+                api.products_v2()
+                    .get(GetProductV2Params {
+                        prn: self.inner.prn.clone(),
+                    })
+                    .await
+                    .context(ApiSnafu)
+            },
+            max_retries,
+            DEFAULT_BASE_DELAY,
+            is_rate_limited,
+        )
+        .await?
+        {
+            Some(product) => serde_json::to_value(&product).context(JsonSerializationSnafu)?,
+            None => EmptyResponseSnafu {
+                context: "products-v2 get",
+            }
+            .fail()?,
+        };
+
+        // devices are still listed by organization and product name
+        let Some(product_name) = product["product"]["name"].as_str() else {
+            return MissingFieldSnafu {
+                context: "products-v2 get",
+                field: "name",
+            }
+            .fail();
+        };
+        let organization_name = global_options.organization_name.unwrap();
+        let search = format!("product_prn:'{}'", self.inner.prn);
+
+        // pages are retried one at a time, so a rate limited page doesn't refetch the earlier ones
+        let cohorts = fetch_pages("cohorts list", self.inner.max_pages, |page| {
+            let api = api.clone();
+            let search = search.clone();
+
+            async move {
+                retry_with_backoff(
+                    || {
+                        let params = ListCohortsParams {
+                            limit: None,
+                            order: None,
+                            search: search.clone(),
+                            page: page.clone(),
+                        };
+                        let api = api.clone();
+
+                        async move { api.cohorts().list(params).await }
+                    },
+                    max_retries,
+                    DEFAULT_BASE_DELAY,
+                    is_api_rate_limited,
+                )
+                .await
+            }
+        });
+
+        let devices = retry_with_backoff(
+            || async {
+                let params = ListDeviceParams {
+                    organization_name: organization_name.clone(),
+                    product_name: product_name.to_string(),
+                };
+
+                api.devices().list(params).await.context(ApiSnafu)
+            },
+            max_retries,
+            DEFAULT_BASE_DELAY,
+            is_rate_limited,
+        );
+
+        let ((cohorts, complete), devices) = tokio::try_join!(cohorts, devices)?;
+
+        if !complete {
+            print_status!(
+                "Stopped after {} pages, more cohorts exist. Use --max-pages to fetch more.",
+                self.inner.max_pages
+            );
+        }
+
+        let devices = match devices {
+            Some(devices) => serde_json::to_value(&devices).context(JsonSerializationSnafu)?,
+            None => EmptyResponseSnafu {
+                context: "devices list",
+            }
+            .fail()?,
+        };
+
+        print_json!(&json!({
+            "prn": self.inner.prn,
+            "name": product_name,
+            "cohorts": cohorts.len(),
+            "devices": devices["devices"].as_array().map_or(0, Vec::len),
+        }));
+
+        Ok(())
+    }
+}
@@ -1,11 +1,17 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
 use super::Command;
+use crate::api::get::GetArgs;
 use crate::api::list::ListArgs;
 use crate::print_json;
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
+use crate::FileSnafu;
 use crate::GlobalOptions;
+use crate::JsonSerializationSnafu;
 use clap::Parser;
 use peridio_sdk::api::releases::{
     CreateReleaseParams, GetReleaseParams, ListReleasesParams, UpdateReleaseParams,
@@ -19,6 +25,7 @@ pub enum ReleasesCommand {
     Create(Command<CreateCommand>),
     List(Command<ListCommand>),
     Get(Command<GetCommand>),
+    Promote(Command<PromoteCommand>),
     Update(Command<UpdateCommand>),
 }
 
@@ -28,6 +35,7 @@ impl ReleasesCommand {
             Self::Create(cmd) => cmd.run(global_options).await,
             Self::List(cmd) => cmd.run(global_options).await,
             Self::Get(cmd) => cmd.run(global_options).await,
+            Self::Promote(cmd) => cmd.run(global_options).await,
             Self::Update(cmd) => cmd.run(global_options).await,
         }
     }
@@ -170,40 +178,57 @@ impl Command<CreateCommand> {
 
         match api.releases().create(params).await.context(ApiSnafu)? {
             Some(release) => print_json!(&release),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "releases create",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
 
+/// List releases.
+///
+/// Instead of writing a `--search` query, releases can be filtered by cohort with `--cohort-prn`. When both are given they are combined with `and`.
 #[derive(Parser, Debug)]
+#[command(mut_arg("search", |arg| arg.required(false).required_unless_present_any(["cohort_prn", "since", "until"])))]
 pub struct ListCommand {
     #[clap(flatten)]
     list_args: ListArgs,
+
+    /// Only list releases within this cohort.
+    #[arg(
+        long,
+        value_parser = PRNValueParser::new(PRNType::Cohort)
+    )]
+    cohort_prn: Option<String>,
 }
 
 impl Command<ListCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let params = ListReleasesParams {
-            limit: self.inner.list_args.limit,
-            order: self.inner.list_args.order,
-            search: self.inner.list_args.search,
-            page: self.inner.list_args.page,
-        };
-
         let api = Api::new(ApiOptions {
             api_key: global_options.api_key.unwrap(),
             endpoint: global_options.base_url,
             ca_bundle_path: global_options.ca_path,
         });
 
-        match api.releases().list(params).await.context(ApiSnafu)? {
-            Some(release) => print_json!(&release),
-            None => panic!(),
-        }
-
-        Ok(())
+        let list_args = &self.inner.list_args;
+        let search = list_args.search_query(&[("cohort_prn", self.inner.cohort_prn.as_deref())]);
+
+        list_args
+            .print_pages("releases list", |page| {
+                let params = ListReleasesParams {
+                    limit: list_args.limit,
+                    order: list_args.order.clone(),
+                    search: search.clone(),
+                    page,
+                };
+                let api = api.clone();
+
+                async move { api.releases().list(params).await }
+            })
+            .await
     }
 }
 
@@ -215,6 +240,9 @@ pub struct GetCommand {
         value_parser = PRNValueParser::new(PRNType::Release)
     )]
     prn: String,
+
+    #[clap(flatten)]
+    get_args: GetArgs,
 }
 
 impl Command<GetCommand> {
@@ -230,14 +258,152 @@ impl Command<GetCommand> {
         });
 
         match api.releases().get(params).await.context(ApiSnafu)? {
+            Some(release) => self.inner.get_args.print(&release)?,
+            None => EmptyResponseSnafu {
+                context: "releases get",
+            }
+            .fail()?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Roll a release out further by raising its phase value.
+///
+/// The phase value can only move forward. Promoting to 1 (100%) is permanent, so it asks for confirmation unless --yes is given.
+#[derive(Parser, Debug)]
+pub struct PromoteCommand {
+    /// The PRN of the release to promote.
+    #[arg(
+        long,
+        value_parser = PRNValueParser::new(PRNType::Release)
+    )]
+    prn: String,
+
+    /// The new phase value, see `releases update --help` for its meaning.
+    #[arg(long, value_parser = parse_phase_value)]
+    phase_value: f64,
+
+    /// Do not ask for confirmation when promoting to 1 (100%).
+    #[arg(long, short = 'y')]
+    yes: bool,
+}
+
+impl Command<PromoteCommand> {
+    async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let api = Api::new(ApiOptions {
+            api_key: global_options.api_key.unwrap(),
+            endpoint: global_options.base_url,
+            ca_bundle_path: global_options.ca_path,
+        });
+
+        let params = GetReleaseParams {
+            prn: self.inner.prn.clone(),
+        };
+
+        let release = match api.releases().get(params).await.context(ApiSnafu)? {
+            Some(release) => serde_json::to_value(release).context(JsonSerializationSnafu)?,
+            None => EmptyResponseSnafu {
+                context: "releases get",
+            }
+            .fail()?,
+        };
+
+        let current = release["release"]["phase_value"].as_f64();
+        let phase_value = self.inner.phase_value;
+
+        if current == Some(1.0) {
+            return Err(Error::ReleasePromotion {
+                reason: "the release is already rolled out to 100% and can no longer change"
+                    .to_string(),
+            });
+        }
+
+        if let Some(current) = current {
+            // percents and device counts can't be compared without knowing the cohort's size,
+            // only 1 (100%) is known to be an increase from either
+            if phase_value != 1.0 && (current <= 1.0) != (phase_value <= 1.0) {
+                return Err(Error::ReleasePromotion {
+                    reason: format!(
+                        "the phase value is currently {current}, it can't be switched between a percent and a device count"
+                    ),
+                });
+            }
+
+            if phase_value < current {
+                return Err(Error::ReleasePromotion {
+                    reason: format!("the phase value can only increase, it is currently {current}"),
+                });
+            }
+        }
+
+        if phase_value == 1.0 && !self.inner.yes && !confirm_full_rollout(&self.inner.prn)? {
+            return Err(Error::ReleasePromotion {
+                reason: "aborted".to_string(),
+            });
+        }
+
+        let params = UpdateReleaseParams {
+            prn: self.inner.prn,
+            description: None,
+            disabled: None,
+            name: None,
+            next_release_prn: None,
+            phase_mode: None,
+            phase_tags: None,
+            phase_value: Some(phase_value),
+            required: None,
+            schedule_date: None,
+            version: None,
+            version_requirement: None,
+        };
+
+        match api.releases().update(params).await.context(ApiSnafu)? {
             Some(release) => print_json!(&release),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "releases update",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
 
+/// Parse a phase value, either a percent in [0.0, 1.0] or a device count of at least 2.
+fn parse_phase_value(value: &str) -> Result<f64, String> {
+    let phase_value: f64 = value
+        .parse()
+        .map_err(|_| format!("'{value}' is not a number"))?;
+
+    if (0.0..=1.0).contains(&phase_value) || (phase_value >= 2.0 && phase_value.fract() == 0.0) {
+        Ok(phase_value)
+    } else {
+        Err("expected a percent in [0.0, 1.0] or a whole device count of at least 2".to_string())
+    }
+}
+
+/// Ask on stderr whether to roll release `prn` out to 100%, reading the answer from stdin.
+fn confirm_full_rollout(prn: &str) -> Result<bool, Error> {
+    if !io::stdin().is_terminal() {
+        return Err(Error::ReleasePromotion {
+            reason: "promoting to 1 (100%) is permanent, pass --yes to confirm".to_string(),
+        });
+    }
+
+    eprint!("Promoting {prn} to 1 (100%) is permanent. Continue? [y/N] ");
+    let _ = io::stderr().flush();
+
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .context(FileSnafu)?;
+
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
 #[derive(Parser, Debug)]
 pub struct UpdateCommand {
     /// The PRN of the resource to update.
@@ -347,9 +513,29 @@ impl Command<UpdateCommand> {
 
         match api.releases().update(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "releases update",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_phase_value_accepts_percents_and_device_counts() {
+        assert_eq!(parse_phase_value("0"), Ok(0.0));
+        assert_eq!(parse_phase_value("0.2"), Ok(0.2));
+        assert_eq!(parse_phase_value("1"), Ok(1.0));
+        assert_eq!(parse_phase_value("40"), Ok(40.0));
+
+        for value in ["-0.5", "1.5", "2.7", "NaN", "inf", "twenty"] {
+            assert!(parse_phase_value(value).is_err(), "{value} was accepted");
+        }
+    }
+}
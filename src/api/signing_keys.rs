@@ -1,19 +1,30 @@
 use std::fs;
+use std::path::Path;
 
 use super::Command;
+use crate::api::get::GetArgs;
 use crate::api::list::ListArgs;
+use crate::config::config_v2::{ConfigV2, SigningKeyPairV2};
+use crate::config::{write_atomic, Config};
 use crate::print_json;
+use crate::print_status;
+use crate::utils::signing;
+use crate::utils::signing::SigningKeyAlgorithm;
+use crate::utils::write_private_key;
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
+use crate::utils::{Style, StyledStr};
 use crate::ApiSnafu;
+use crate::ConfigParseSnafu;
+use crate::ConfigReadSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
+use crate::FileSnafu;
+use crate::FileWriteSnafu;
 use crate::GlobalOptions;
+use crate::JsonSerializationSnafu;
 use crate::NonExistingPathSnafu;
-use base64::engine::general_purpose;
-use base64::Engine;
 use clap::Parser;
-use ed25519_dalek::pkcs8::DecodePublicKey;
-use ed25519_dalek::VerifyingKey;
 use peridio_sdk::api::signing_keys::CreateSigningKeyParams;
 use peridio_sdk::api::signing_keys::DeleteSigningKeyParams;
 use peridio_sdk::api::signing_keys::GetSigningKeyParams;
@@ -24,6 +35,7 @@ use snafu::ResultExt;
 
 #[derive(Parser, Debug)]
 pub enum SigningKeysCommand {
+    AddToConfig(Command<AddToConfigCommand>),
     Create(Command<CreateCommand>),
     Delete(Command<DeleteCommand>),
     Get(Command<GetCommand>),
@@ -33,6 +45,7 @@ pub enum SigningKeysCommand {
 impl SigningKeysCommand {
     pub async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
         match self {
+            Self::AddToConfig(cmd) => cmd.run(global_options).await,
             Self::Create(cmd) => cmd.run(global_options).await,
             Self::Delete(cmd) => cmd.run(global_options).await,
             Self::Get(cmd) => cmd.run(global_options).await,
@@ -41,6 +54,86 @@ impl SigningKeysCommand {
     }
 }
 
+/// Add an existing signing key pair to the signing key pairs of your Peridio CLI config.
+#[derive(Parser, Debug)]
+pub struct AddToConfigCommand {
+    /// The name to save the signing key pair as in your Peridio CLI config.
+    #[arg(long)]
+    name: String,
+    /// The PRN of the signing key.
+    #[arg(
+        long,
+        value_parser = PRNValueParser::new(PRNType::SigningKey)
+    )]
+    signing_key_prn: String,
+    /// The path to the private key pem file of the signing key.
+    #[arg(long)]
+    signing_key_private_path: String,
+}
+
+impl Command<AddToConfigCommand> {
+    async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let private_key_path = fs::canonicalize(&self.inner.signing_key_private_path).context(
+            NonExistingPathSnafu {
+                path: &self.inner.signing_key_private_path,
+            },
+        )?;
+
+        let config_dir_path = Config::directory(&global_options.config_directory)?
+            .ok_or(Error::ConfigDirectoryUnknown)?;
+
+        fs::create_dir_all(&config_dir_path).context(FileWriteSnafu {
+            path: &config_dir_path,
+        })?;
+
+        let config_path = config_dir_path.join("config.json");
+
+        let mut config = if config_path.exists() {
+            let config_file =
+                fs::read_to_string(&config_path).context(ConfigReadSnafu { path: &config_path })?;
+
+            serde_json::from_str::<ConfigV2>(&config_file)
+                .context(ConfigParseSnafu { path: &config_path })?
+        } else {
+            ConfigV2::default()
+        };
+
+        let signing_key_pairs = config
+            .signing_key_pairs
+            .get_or_insert_with(Default::default);
+
+        if signing_key_pairs.contains_key(&self.inner.name) {
+            return Err(Error::SigningKeyPairExists {
+                name: self.inner.name,
+                path: config_path,
+            });
+        }
+
+        signing_key_pairs.insert(
+            self.inner.name.clone(),
+            SigningKeyPairV2 {
+                signing_key_prn: self.inner.signing_key_prn,
+                signing_key_private_path: private_key_path.to_string_lossy().into_owned(),
+            },
+        );
+
+        let contents = serde_json::to_vec_pretty(&config).context(JsonSerializationSnafu)?;
+        write_atomic(&config_path, &contents).context(FileWriteSnafu { path: &config_path })?;
+
+        let mut msg = StyledStr::new();
+        msg.push_str(Some(Style::Success), "success: ".to_string());
+        msg.push_str(
+            None,
+            format!(
+                "Signing key pair '{}' added to {}.",
+                self.inner.name,
+                config_path.display()
+            ),
+        );
+        msg.print_success()
+    }
+}
+
 #[derive(Parser, Debug)]
 pub struct CreateCommand {
     /// The public key raw file contents.
@@ -50,8 +143,8 @@ pub struct CreateCommand {
         long,
         conflicts_with = "key",
         conflicts_with = "path",
-        required_unless_present = "key",
-        required_unless_present = "path"
+        conflicts_with = "generate_private_key",
+        required_unless_present_any = ["key", "path", "generate_private_key"]
     )]
     value: Option<String>,
     /// The resource's name, meant to be displayable to users.
@@ -68,19 +161,38 @@ pub struct CreateCommand {
         long,
         conflicts_with = "value",
         conflicts_with = "path",
-        required_unless_present = "value",
-        required_unless_present = "path"
+        conflicts_with = "generate_private_key",
+        required_unless_present_any = ["value", "path", "generate_private_key"]
     )]
     key: Option<String>,
-    /// The path to the public key pem file.
+    /// The path to the public key pem file. Ed25519 and ECDSA P-256 keys are supported.
     #[arg(
         long,
         conflicts_with = "key",
         conflicts_with = "value",
-        required_unless_present = "key",
-        required_unless_present = "value"
+        conflicts_with = "generate_private_key",
+        required_unless_present_any = ["key", "value", "generate_private_key"]
     )]
     path: Option<String>,
+    /// The path to the private key pem file the public key at `--path` belongs to.
+    ///
+    /// When given, the signing key is only created if the two keys form a pair.
+    #[arg(long, requires = "path")]
+    signing_key_private: Option<String>,
+    /// Generate a new key pair, write its PKCS#8 private key pem to this path and create the signing key from its public key.
+    ///
+    /// The file must not already exist.
+    #[arg(
+        long,
+        conflicts_with = "key",
+        conflicts_with = "value",
+        conflicts_with = "path",
+        required_unless_present_any = ["key", "value", "path"]
+    )]
+    generate_private_key: Option<String>,
+    /// The algorithm of the key pair to generate. [default: ed25519]
+    #[arg(long, value_enum, requires = "generate_private_key")]
+    algorithm: Option<SigningKeyAlgorithm>,
 }
 
 impl Command<CreateCommand> {
@@ -88,12 +200,34 @@ impl Command<CreateCommand> {
         let value = if let Some(path) = self.inner.path {
             let verifying_key_pub =
                 fs::read_to_string(&path).context(NonExistingPathSnafu { path: &path })?;
-            let verifying_key = VerifyingKey::from_public_key_pem(&verifying_key_pub)
-                .expect("invalid public key PEM");
 
-            let raw_bytes = verifying_key.as_bytes();
+            let value = signing::public_key_value_from_pem(&verifying_key_pub)?;
+
+            if let Some(private_key_path) = self.inner.signing_key_private {
+                let signing_key_private =
+                    fs::read_to_string(&private_key_path).context(NonExistingPathSnafu {
+                        path: &private_key_path,
+                    })?;
 
-            general_purpose::STANDARD.encode(raw_bytes)
+                if signing::public_key_value_from_private_pem(&signing_key_private)? != value {
+                    return Err(Error::SigningKey {
+                        reason: format!(
+                            "the public key at {path} does not match the private key at {private_key_path}"
+                        ),
+                    });
+                }
+            }
+
+            value
+        } else if let Some(private_key_path) = self.inner.generate_private_key {
+            let (private_key_pem, value) =
+                signing::generate_key_pair(self.inner.algorithm.unwrap_or_default())?;
+
+            write_private_key(Path::new(&private_key_path), &private_key_pem).context(FileSnafu)?;
+
+            print_status!("Private key written to {private_key_path}");
+
+            value
         } else if let Some(key) = self.inner.key {
             fs::read_to_string(&key)
                 .context(NonExistingPathSnafu { path: &key })?
@@ -117,7 +251,10 @@ impl Command<CreateCommand> {
 
         match api.signing_keys().create(params).await.context(ApiSnafu)? {
             Some(key) => print_json!(&key),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "signing-keys create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -132,6 +269,9 @@ pub struct GetCommand {
         value_parser = PRNValueParser::new(PRNType::SigningKey)
     )]
     prn: String,
+
+    #[clap(flatten)]
+    get_args: GetArgs,
 }
 
 impl Command<GetCommand> {
@@ -147,8 +287,11 @@ impl Command<GetCommand> {
         });
 
         match api.signing_keys().get(params).await.context(ApiSnafu)? {
-            Some(key) => print_json!(&key),
-            None => panic!(),
+            Some(key) => self.inner.get_args.print(&key)?,
+            None => EmptyResponseSnafu {
+                context: "signing-keys get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -163,25 +306,27 @@ pub struct ListCommand {
 
 impl Command<ListCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let params = ListSigningKeysParams {
-            limit: self.inner.list_args.limit,
-            order: self.inner.list_args.order,
-            search: self.inner.list_args.search,
-            page: self.inner.list_args.page,
-        };
-
         let api = Api::new(ApiOptions {
             api_key: global_options.api_key.unwrap(),
             endpoint: global_options.base_url,
             ca_bundle_path: global_options.ca_path,
         });
 
-        match api.signing_keys().list(params).await.context(ApiSnafu)? {
-            Some(signing_key) => print_json!(&signing_key),
-            None => panic!(),
-        }
+        let list_args = &self.inner.list_args;
 
-        Ok(())
+        list_args
+            .print_pages("signing-keys list", |page| {
+                let params = ListSigningKeysParams {
+                    limit: list_args.limit,
+                    order: list_args.order.clone(),
+                    search: list_args.search_query(&[]),
+                    page,
+                };
+                let api = api.clone();
+
+                async move { api.signing_keys().list(params).await }
+            })
+            .await
     }
 }
 
@@ -2,10 +2,13 @@ use std::thread::sleep;
 use std::time::{Duration, Instant};
 
 use super::Command;
+use crate::api::get::GetArgs;
 use crate::api::list::ListArgs;
 use crate::print_json;
+use crate::print_status;
 use crate::utils::{PRNType, PRNValueParser};
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use clap::Parser;
@@ -22,6 +25,7 @@ pub enum TunnelsCommand {
     Create(Command<CreateCommand>),
     Get(Command<GetCommand>),
     List(Command<ListCommand>),
+    Open(Command<OpenCommand>),
     Update(Command<UpdateCommand>),
 }
 
@@ -31,6 +35,7 @@ impl TunnelsCommand {
             Self::Create(cmd) => cmd.run(global_options).await,
             Self::Get(cmd) => cmd.run(global_options).await,
             Self::List(cmd) => cmd.run(global_options).await,
+            Self::Open(cmd) => cmd.run(global_options).await,
             Self::Update(cmd) => cmd.run(global_options).await,
         }
     }
@@ -109,14 +114,20 @@ impl Command<CreateCommand> {
                                     sleep(Duration::from_secs(sleep_secs))
                                 }
                             }
-                            None => panic!(),
+                            None => EmptyResponseSnafu {
+                                context: "tunnels get",
+                            }
+                            .fail()?,
                         }
                     }
                 } else {
                     print_json!(&response);
                 }
             }
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "tunnels create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -131,6 +142,9 @@ pub struct GetCommand {
         value_parser = PRNValueParser::new(PRNType::Tunnel)
     )]
     prn: String,
+
+    #[clap(flatten)]
+    get_args: GetArgs,
 }
 
 impl Command<GetCommand> {
@@ -146,27 +160,104 @@ impl Command<GetCommand> {
         });
 
         match api.tunnels().get(params).await.context(ApiSnafu)? {
-            Some(tunnel) => print_json!(&tunnel),
-            None => panic!(),
+            Some(tunnel) => self.inner.get_args.print(&tunnel)?,
+            None => EmptyResponseSnafu {
+                context: "tunnels get",
+            }
+            .fail()?,
         }
 
         Ok(())
     }
 }
 
+/// List tunnels.
+///
+/// Instead of writing a `--search` query, tunnels can be filtered by device with `--device-prn` and by state with `--state`. When combined they are joined with `and`.
 #[derive(Parser, Debug)]
+#[command(mut_arg("search", |arg| arg.required(false).required_unless_present_any(["device_prn", "state", "since", "until"])))]
 pub struct ListCommand {
     #[clap(flatten)]
     list_args: ListArgs,
+
+    /// Only list tunnels to this device.
+    #[arg(
+        long,
+        value_parser = PRNValueParser::new(PRNType::Device)
+    )]
+    device_prn: Option<String>,
+
+    /// Only list tunnels in this state, e.g. "open" or "closed".
+    #[arg(long)]
+    state: Option<String>,
 }
 
 impl Command<ListCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let params = ListTunnelsParams {
-            limit: self.inner.list_args.limit,
-            order: self.inner.list_args.order,
-            search: self.inner.list_args.search,
-            page: self.inner.list_args.page,
+        let api = Api::new(ApiOptions {
+            api_key: global_options.api_key.unwrap(),
+            endpoint: global_options.base_url,
+            ca_bundle_path: global_options.ca_path,
+        });
+
+        let list_args = &self.inner.list_args;
+        let search = list_args.search_query(&[
+            ("device_prn", self.inner.device_prn.as_deref()),
+            ("state", self.inner.state.as_deref()),
+        ]);
+
+        list_args
+            .print_pages("tunnels list", |page| {
+                let params = ListTunnelsParams {
+                    limit: list_args.limit,
+                    order: list_args.order.clone(),
+                    search: search.clone(),
+                    page,
+                };
+                let api = api.clone();
+
+                async move { api.tunnels().list(params).await }
+            })
+            .await
+    }
+}
+
+/// Create a tunnel and keep it open until interrupted.
+///
+/// The tunnel is printed once created and again whenever its state changes. Ctrl-C closes the tunnel before exiting.
+#[derive(Parser, Debug)]
+pub struct OpenCommand {
+    /// An optional list of CIDR blocks that can use the resource.
+    #[arg(long)]
+    cidr_block_allowlist: Option<Vec<String>>,
+
+    /// The PRN of the device you wish to open the tunnel to.
+    #[arg(
+        long,
+        value_parser = PRNValueParser::new(PRNType::Device)
+    )]
+    device_prn: String,
+
+    /// The port of the device that being used for the service.
+    #[arg(long)]
+    device_tunnel_port: u16,
+
+    /// The length of time in seconds for the tunnel to live.
+    #[arg(long)]
+    ttl: Option<u16>,
+
+    /// Number of seconds between checks of the tunnel's state (1..3600)
+    #[arg(long, default_value_t = 5, value_parser=less_than_3600)]
+    poll_interval: u16,
+}
+
+impl Command<OpenCommand> {
+    async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
+        let params = CreateTunnelParams {
+            cidr_block_allowlist: self.inner.cidr_block_allowlist,
+            device_prn: self.inner.device_prn,
+            device_tunnel_port: self.inner.device_tunnel_port,
+            ttl: self.inner.ttl,
         };
 
         let api = Api::new(ApiOptions {
@@ -175,13 +266,84 @@ impl Command<ListCommand> {
             ca_bundle_path: global_options.ca_path,
         });
 
-        match api.tunnels().list(params).await.context(ApiSnafu)? {
-            Some(tunnel) => print_json!(&tunnel),
-            None => panic!(),
+        let Some(response) = api.tunnels().create(params).await.context(ApiSnafu)? else {
+            return EmptyResponseSnafu {
+                context: "tunnels create",
+            }
+            .fail();
+        };
+
+        let prn = response.tunnel.prn.clone();
+        print_json!(&response);
+        print_status!(
+            "Tunnel {prn} is {}, press Ctrl-C to close it.",
+            response.tunnel.state
+        );
+
+        let poll_interval = Duration::from_secs(self.inner.poll_interval.into());
+
+        match watch_tunnel(&api, &prn, response.tunnel.state, poll_interval).await {
+            // The tunnel closed on its own, there is nothing left to tear down.
+            Ok(false) => Ok(()),
+            interrupted => {
+                print_status!("Closing tunnel {prn}");
+
+                let params = UpdateTunnelParams {
+                    prn,
+                    state: Some("closed".to_string()),
+                    ttl: None,
+                };
+
+                match api.tunnels().update(params).await.context(ApiSnafu)? {
+                    Some(response) => print_json!(&response),
+                    None => EmptyResponseSnafu {
+                        context: "tunnels update",
+                    }
+                    .fail()?,
+                }
+
+                interrupted.map(|_| ())
+            }
         }
+    }
+}
 
-        Ok(())
+/// Polls the tunnel until Ctrl-C is pressed or it is closed, printing it whenever its state
+/// changes. Returns whether the tunnel still needs to be closed.
+async fn watch_tunnel(
+    api: &Api,
+    prn: &str,
+    mut state: String,
+    poll_interval: Duration,
+) -> Result<bool, Error> {
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    while state != "closed" {
+        tokio::select! {
+            _ = &mut ctrl_c => return Ok(true),
+            _ = tokio::time::sleep(poll_interval) => {}
+        }
+
+        let params = GetTunnelParams {
+            prn: prn.to_string(),
+        };
+
+        match api.tunnels().get(params).await.context(ApiSnafu)? {
+            Some(response) if response.tunnel.state != state => {
+                print_status!("Tunnel {prn} is now {}", response.tunnel.state);
+                print_json!(&response);
+                state = response.tunnel.state;
+            }
+            Some(_) => {}
+            None => EmptyResponseSnafu {
+                context: "tunnels get",
+            }
+            .fail()?,
+        }
     }
+
+    Ok(false)
 }
 
 #[derive(Parser, Debug)]
@@ -230,7 +392,10 @@ impl Command<UpdateCommand> {
 
         match api.tunnels().update(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "tunnels update",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -11,12 +11,19 @@ use directories::ProjectDirs;
 use flate2::read::GzDecoder;
 use futures_util::StreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::ClientBuilder;
+use reqwest::{ClientBuilder, StatusCode};
 use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use tar::Archive;
 
+use crate::print_json;
+use crate::utils::{Style, StyledStr};
 use crate::Error;
 
+/// Exit code of `upgrade --check` when a newer version is available.
+const UPGRADE_AVAILABLE_EXIT_CODE: i32 = 10;
+
 #[derive(Deserialize, Debug)]
 struct GithubAssetResponse {
     browser_download_url: String,
@@ -34,55 +41,159 @@ pub struct UpgradeCommand {
     /// Controls what version to upgrade to.
     ///
     /// If not specified, the latest version will be used.
-    #[arg(long)]
+    #[arg(long, value_parser = version_value_parser)]
     version: Option<String>,
+
+    /// Allow `--version` to install a version older than the current one.
+    #[arg(long, requires = "version")]
+    allow_downgrade: bool,
+
+    /// Only report whether a newer version is available, without downloading it.
+    ///
+    /// Exits with code 10 when an upgrade is available.
+    #[arg(long, conflicts_with = "version")]
+    check: bool,
+
+    /// Install the download even when the release publishes no checksum to verify it against.
+    ///
+    /// Only needed for releases published before checksums were introduced.
+    #[arg(long, conflicts_with = "check")]
+    skip_checksum: bool,
 }
 
 impl UpgradeCommand {
     pub async fn run(self) -> Result<(), Error> {
+        if self.check {
+            return Self::check().await;
+        }
+
         if let Some(proj_dirs) = ProjectDirs::from("", "", "peridio") {
             let cache_dir = proj_dirs.cache_dir();
 
             create_dir_all(cache_dir).unwrap();
 
-            if let Ok(resp) = Self::get_release_info(self.version).await {
-                let current_version = env!("CARGO_PKG_VERSION");
+            let requested_version = self.version.clone();
 
-                // no need to update
-                if resp.tag_name == current_version {
-                    println!("CLI already up to date");
-                    return Ok(());
+            let resp = Self::get_release_info(self.version).await.map_err(|err| {
+                match (err.status(), requested_version) {
+                    (Some(StatusCode::NOT_FOUND), Some(version)) => Error::Upgrade {
+                        reason: format!("version {version} does not exist"),
+                    },
+                    _ => Error::Upgrade {
+                        reason: format!("failed to fetch release information: {err}"),
+                    },
                 }
+            })?;
 
-                let name = format!("peridio-{}_{}.tar.gz", resp.tag_name, env!("TARGET"));
-
-                let github_asset_info = match resp.assets.iter().find(|&x| x.name == name) {
-                    Some(x) => x,
-                    None => {
-                        println!(
-                            "version {} does not include a pre-built binary for target {}",
-                            resp.tag_name,
-                            env!("TARGET")
-                        );
-                        return Ok(());
-                    }
-                };
-
-                if let Err(message) = Self::download_update(cache_dir, github_asset_info).await {
-                    println!("{message}");
-                    return Ok(());
-                }
+            let current_version = env!("CARGO_PKG_VERSION");
+
+            // no need to update
+            if resp.tag_name == current_version {
+                println!("CLI already up to date");
+                return Ok(());
+            }
+
+            let downgrade = is_newer(current_version, &resp.tag_name);
+
+            if downgrade && !self.allow_downgrade {
+                return Err(Error::Upgrade {
+                    reason: format!(
+                        "{} is older than the current version {current_version}, pass --allow-downgrade to install it anyway",
+                        resp.tag_name
+                    ),
+                });
+            }
 
-                if let Err(message) = Self::apply_update(cache_dir, &resp) {
-                    println!("{message}");
+            let name = format!("peridio-{}_{}.tar.gz", resp.tag_name, env!("TARGET"));
+
+            let github_asset_info = match resp.assets.iter().find(|&x| x.name == name) {
+                Some(x) => x,
+                None => {
+                    println!(
+                        "version {} does not include a pre-built binary for target {}",
+                        resp.tag_name,
+                        env!("TARGET")
+                    );
                     return Ok(());
                 }
+            };
+
+            let checksum_name = format!("{name}.sha256");
+
+            let expected_hash = match resp.assets.iter().find(|&x| x.name == checksum_name) {
+                Some(checksum_asset) => Some(
+                    Self::get_checksum(checksum_asset)
+                        .await
+                        .map_err(|reason| Error::Upgrade { reason })?,
+                ),
+                None if self.skip_checksum => {
+                    let mut warning = StyledStr::new();
+                    warning.push_str(Some(Style::Warning), "warning: ".to_string());
+                    warning.push_str(
+                        None,
+                        format!(
+                            "{name} has no published checksum, installing it unverified because of --skip-checksum."
+                        ),
+                    );
+                    let _ = warning.print_err();
+
+                    None
+                }
+                None => {
+                    return Err(Error::Upgrade {
+                        reason: format!(
+                            "no published checksum for {name}, pass --skip-checksum to install it anyway"
+                        ),
+                    })
+                }
+            };
+
+            Self::download_update(cache_dir, github_asset_info, expected_hash.as_deref())
+                .await
+                .map_err(|reason| Error::Upgrade { reason })?;
+
+            println!(
+                "{} CLI from {current_version} to {}",
+                if downgrade {
+                    "Downgrading"
+                } else {
+                    "Upgrading"
+                },
+                resp.tag_name
+            );
+
+            if let Err(message) = Self::apply_update(cache_dir, &resp) {
+                println!("{message}");
+                return Ok(());
             }
         }
 
         Ok(())
     }
 
+    async fn check() -> Result<(), Error> {
+        let resp = Self::get_release_info(None)
+            .await
+            .map_err(|err| Error::Upgrade {
+                reason: format!("failed to fetch the latest release: {err}"),
+            })?;
+
+        let current_version = env!("CARGO_PKG_VERSION");
+        let upgrade_available = is_newer(&resp.tag_name, current_version);
+
+        print_json!(&json!({
+            "current_version": current_version,
+            "latest_version": resp.tag_name,
+            "upgrade_available": upgrade_available,
+        }));
+
+        if upgrade_available {
+            std::process::exit(UPGRADE_AVAILABLE_EXIT_CODE)
+        }
+
+        Ok(())
+    }
+
     fn apply_update(path: &Path, github_response: &GithubResponse) -> Result<(), String> {
         let update_file = path.join("peridio");
 
@@ -107,9 +218,12 @@ impl UpgradeCommand {
         Ok(())
     }
 
+    /// Downloads and unpacks a release asset. When `expected_hash` is given, nothing is unpacked
+    /// unless the download's SHA256 matches it.
     async fn download_update(
         download_path: &Path,
         github_asset_info: &GithubAssetResponse,
+        expected_hash: Option<&str>,
     ) -> Result<(), String> {
         let client = ClientBuilder::new().use_rustls_tls().build().unwrap();
         let url = &github_asset_info.browser_download_url;
@@ -150,6 +264,17 @@ impl UpgradeCommand {
 
         pb.finish_and_clear();
 
+        if let Some(expected_hash) = expected_hash {
+            let hash = format!("{:x}", Sha256::digest(buff.get_ref()));
+
+            if hash != expected_hash {
+                return Err(format!(
+                    "checksum mismatch for {}: expected {expected_hash}, got {hash}",
+                    github_asset_info.name
+                ));
+            }
+        }
+
         buff.rewind().unwrap();
 
         let gz = GzDecoder::new(&mut buff);
@@ -163,6 +288,24 @@ impl UpgradeCommand {
         Ok(())
     }
 
+    /// Fetches a `sha256sum` style checksum file and returns the lowercase hex hash in it.
+    async fn get_checksum(checksum_asset: &GithubAssetResponse) -> Result<String, String> {
+        let client = ClientBuilder::new().use_rustls_tls().build().unwrap();
+        let url = &checksum_asset.browser_download_url;
+
+        let checksum = client
+            .get(url)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|_| format!("Failed to GET from '{url}'"))?
+            .text()
+            .await
+            .map_err(|_| format!("Failed to read the checksum from '{url}'"))?;
+
+        parse_checksum(&checksum).ok_or(format!("Malformed checksum file at '{url}'"))
+    }
+
     async fn get_release_info(version: Option<String>) -> Result<GithubResponse, reqwest::Error> {
         let client = ClientBuilder::new().use_rustls_tls().build()?;
         let url = if let Some(version) = version {
@@ -177,7 +320,88 @@ impl UpgradeCommand {
             .header("User-Agent", "peridio/peridio-cli")
             .send()
             .await?
+            .error_for_status()?
             .json::<GithubResponse>()
             .await
     }
 }
+
+/// Parses a `major.minor.patch` version, with or without a leading `v`.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let mut parts = version.split('.').map(|part| part.parse().ok());
+
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) => {
+            Some((major, minor, patch))
+        }
+        _ => None,
+    }
+}
+
+/// Accepts `major.minor.patch` with an optional leading `v`, which is dropped to match release tags.
+fn version_value_parser(version: &str) -> Result<String, String> {
+    parse_version(version)
+        .map(|(major, minor, patch)| format!("{major}.{minor}.{patch}"))
+        .ok_or_else(|| "expected a version like 1.2.3".to_string())
+}
+
+/// Returns the hash from the first field of a `sha256sum` style line.
+fn parse_checksum(checksum: &str) -> Option<String> {
+    checksum
+        .split_whitespace()
+        .next()
+        .filter(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(str::to_ascii_lowercase)
+}
+
+/// Whether `candidate` is newer than `current`. Versions that can't be parsed are only
+/// compared for equality.
+fn is_newer(candidate: &str, current: &str) -> bool {
+    match (parse_version(candidate), parse_version(current)) {
+        (Some(candidate), Some(current)) => candidate > current,
+        _ => candidate != current,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version_accepts_semver_with_optional_v() {
+        assert_eq!(parse_version("0.27.0"), Some((0, 27, 0)));
+        assert_eq!(parse_version("v1.2.3"), Some((1, 2, 3)));
+        assert_eq!(parse_version("1.2"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+        assert_eq!(parse_version("1.2.x"), None);
+    }
+
+    #[test]
+    fn version_value_parser_normalizes_to_release_tags() {
+        assert_eq!(version_value_parser("v0.27.0"), Ok("0.27.0".to_string()));
+        assert!(version_value_parser("latest").is_err());
+    }
+
+    #[test]
+    fn parse_checksum_reads_sha256sum_output() {
+        let hash = "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08";
+
+        assert_eq!(
+            parse_checksum(&format!(
+                "{hash}  peridio-0.27.0_x86_64-unknown-linux-gnu.tar.gz\n"
+            )),
+            Some(hash.to_ascii_lowercase())
+        );
+        assert_eq!(parse_checksum("not-a-hash  file.tar.gz"), None);
+        assert_eq!(parse_checksum(""), None);
+    }
+
+    #[test]
+    fn is_newer_compares_numerically() {
+        assert!(is_newer("0.27.10", "0.27.9"));
+        assert!(is_newer("1.0.0", "0.99.99"));
+        assert!(!is_newer("0.27.0", "0.27.0"));
+        assert!(!is_newer("0.26.1", "0.27.0"));
+    }
+}
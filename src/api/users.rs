@@ -2,6 +2,7 @@ use super::Command;
 use crate::api::CliCommands;
 use crate::print_json;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use clap::Parser;
@@ -34,7 +35,10 @@ impl Command<MeCommand> {
             missing_arguments.push("--api-key".to_owned());
         }
 
-        CliCommands::print_missing_arguments(missing_arguments);
+        CliCommands::print_missing_arguments(
+            missing_arguments,
+            global_options.loaded_profile.as_deref(),
+        );
 
         let api = Api::new(ApiOptions {
             api_key: global_options.api_key.unwrap(),
@@ -44,7 +48,10 @@ impl Command<MeCommand> {
 
         match api.users().me().await.context(ApiSnafu)? {
             Some(users_me) => print_json!(&users_me),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "users me",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -0,0 +1,37 @@
+use clap::Parser;
+use serde_json::json;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::print_json;
+use crate::Error;
+
+/// Print build information as JSON.
+///
+/// Unlike `--version`, this includes the git commit, the Peridio SDK version, the target triple and when the CLI was built, which helps when reporting bugs.
+#[derive(Parser, Debug)]
+pub struct VersionCommand {}
+
+impl VersionCommand {
+    pub fn run(self) -> Result<(), Error> {
+        let build_timestamp = env!("PERIDIO_CLI_BUILD_TIMESTAMP")
+            .parse()
+            .ok()
+            .and_then(|timestamp| OffsetDateTime::from_unix_timestamp(timestamp).ok())
+            .and_then(|timestamp| timestamp.format(&Rfc3339).ok());
+
+        // both are empty when they couldn't be determined at build time
+        let git_sha = Some(env!("PERIDIO_CLI_GIT_SHA")).filter(|sha| !sha.is_empty());
+        let sdk_version = Some(env!("PERIDIO_SDK_VERSION")).filter(|version| !version.is_empty());
+
+        print_json!(&json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "git_sha": git_sha,
+            "sdk_version": sdk_version,
+            "target": env!("TARGET"),
+            "build_timestamp": build_timestamp,
+        }));
+
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
 use super::Command;
+use crate::api::get::GetArgs;
 use crate::api::list::ListArgs;
 use crate::print_json;
 use crate::utils::PRNType;
 use crate::utils::PRNValueParser;
 use crate::ApiSnafu;
+use crate::EmptyResponseSnafu;
 use crate::Error;
 use crate::GlobalOptions;
 use clap::Parser;
@@ -25,6 +27,7 @@ pub enum WebhooksCommand {
     Get(Command<GetCommand>),
     List(Command<ListCommand>),
     RollSecret(Command<RollSecretCommand>),
+    #[command(alias = "test")]
     TestFire(Command<TestFireCommand>),
     Update(Command<UpdateCommand>),
 }
@@ -82,7 +85,10 @@ impl Command<CreateCommand> {
 
         match api.webhooks().create(params).await.context(ApiSnafu)? {
             Some(webhook) => print_json!(&webhook),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "webhooks create",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -97,25 +103,27 @@ pub struct ListCommand {
 
 impl Command<ListCommand> {
     async fn run(self, global_options: GlobalOptions) -> Result<(), Error> {
-        let params = ListWebhooksParams {
-            limit: self.inner.list_args.limit,
-            order: self.inner.list_args.order,
-            search: self.inner.list_args.search,
-            page: self.inner.list_args.page,
-        };
-
         let api = Api::new(ApiOptions {
             api_key: global_options.api_key.unwrap(),
             endpoint: global_options.base_url,
             ca_bundle_path: global_options.ca_path,
         });
 
-        match api.webhooks().list(params).await.context(ApiSnafu)? {
-            Some(webhook) => print_json!(&webhook),
-            None => panic!(),
-        }
-
-        Ok(())
+        let list_args = &self.inner.list_args;
+
+        list_args
+            .print_pages("webhooks list", |page| {
+                let params = ListWebhooksParams {
+                    limit: list_args.limit,
+                    order: list_args.order.clone(),
+                    search: list_args.search_query(&[]),
+                    page,
+                };
+                let api = api.clone();
+
+                async move { api.webhooks().list(params).await }
+            })
+            .await
     }
 }
 
@@ -127,6 +135,9 @@ pub struct GetCommand {
         value_parser = PRNValueParser::new(PRNType::Webhook)
     )]
     prn: String,
+
+    #[clap(flatten)]
+    get_args: GetArgs,
 }
 
 impl Command<GetCommand> {
@@ -142,8 +153,11 @@ impl Command<GetCommand> {
         });
 
         match api.webhooks().get(params).await.context(ApiSnafu)? {
-            Some(webhook) => print_json!(&webhook),
-            None => panic!(),
+            Some(webhook) => self.inner.get_args.print(&webhook)?,
+            None => EmptyResponseSnafu {
+                context: "webhooks get",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -171,7 +185,10 @@ impl Command<RollSecretCommand> {
 
         match api.webhooks().roll_secret(params).await.context(ApiSnafu)? {
             Some(webhook) => print_json!(&webhook),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "webhooks roll-secret",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -199,7 +216,10 @@ impl Command<TestFireCommand> {
 
         match api.webhooks().test_fire(params).await.context(ApiSnafu)? {
             Some(webhook) => print_json!(&webhook),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "webhooks test-fire",
+            }
+            .fail()?,
         }
 
         Ok(())
@@ -248,7 +268,10 @@ impl Command<UpdateCommand> {
 
         match api.webhooks().update(params).await.context(ApiSnafu)? {
             Some(device) => print_json!(&device),
-            None => panic!(),
+            None => EmptyResponseSnafu {
+                context: "webhooks update",
+            }
+            .fail()?,
         }
 
         Ok(())
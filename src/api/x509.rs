@@ -1,13 +1,17 @@
 use super::Command;
 use crate::print_json;
-use crate::utils::{Style, StyledStr};
+use crate::utils::{write_private_key, Style, StyledStr};
 use crate::{
-    CertParamsCreationSnafu, CertificateCreationSnafu, Error, GlobalOptions, NonExistingPathSnafu,
+    CertParamsCreationSnafu, CertificateCreationSnafu, DateParseSnafu, Error, FileSnafu,
+    FileWriteSnafu, GlobalOptions, NonExistingPathSnafu,
 };
+use ::time::format_description::well_known::Rfc3339;
 use ::time::macros::format_description;
 use ::time::OffsetDateTime;
 use clap::Parser;
-use rcgen::{CertificateParams, DistinguishedName, DnType, IsCa, KeyPair};
+use rcgen::{
+    Certificate, CertificateParams, DistinguishedName, DnType, Ia5String, IsCa, KeyPair, SanType,
+};
 use serde_json::json;
 use snafu::ResultExt;
 use std::env;
@@ -20,15 +24,43 @@ pub struct CreateCommand {
     #[arg(long)]
     common_name: String,
 
+    /// A subject alternative name (SAN) to add to the certificate. Can be repeated.
+    ///
+    /// Values are prefixed with their type: `dns:`, `ip:`, `email:` or `uri:`, e.g. `dns:device.example.com` or `ip:10.0.0.5`.
+    #[arg(long = "san", value_name = "SAN", value_parser = parse_san)]
+    subject_alt_names: Vec<SanType>,
+
     /// Whether this certificate is a Certificate Authority (CA)
     #[arg(long, default_value = "false")]
     is_ca: bool,
 
-    /// The start date of the certificate's validity period (format: YYYY-MM-DD)
+    /// A key usage to add to the certificate. Can be repeated or comma separated.
+    ///
+    /// Defaults to `digital-signature`, or `key-cert-sign,crl-sign` when `--is-ca` is set.
+    #[arg(
+        long = "key-usage",
+        value_name = "KEY_USAGE",
+        value_enum,
+        value_delimiter = ','
+    )]
+    key_usages: Vec<KeyUsage>,
+
+    /// An extended key usage to add to the certificate. Can be repeated or comma separated.
+    ///
+    /// Defaults to `client-auth`.
+    #[arg(
+        long = "extended-key-usage",
+        value_name = "EXTENDED_KEY_USAGE",
+        value_enum,
+        value_delimiter = ','
+    )]
+    extended_key_usages: Vec<ExtendedKeyUsage>,
+
+    /// The start date of the certificate's validity period (format: YYYY-MM-DD or an RFC 3339 datetime such as 2025-12-31T23:59:59Z)
     #[arg(long)]
     start_date: String,
 
-    /// The end date of the certificate's validity period (format: YYYY-MM-DD)
+    /// The end date of the certificate's validity period (format: YYYY-MM-DD or an RFC 3339 datetime such as 2025-12-31T23:59:59Z)
     #[arg(long)]
     end_date: String,
 
@@ -49,6 +81,54 @@ pub struct CreateCommand {
     signer: Option<String>,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum KeyUsage {
+    DigitalSignature,
+    ContentCommitment,
+    KeyEncipherment,
+    DataEncipherment,
+    KeyAgreement,
+    KeyCertSign,
+    CrlSign,
+}
+
+impl From<KeyUsage> for rcgen::KeyUsagePurpose {
+    fn from(key_usage: KeyUsage) -> Self {
+        match key_usage {
+            KeyUsage::DigitalSignature => Self::DigitalSignature,
+            KeyUsage::ContentCommitment => Self::ContentCommitment,
+            KeyUsage::KeyEncipherment => Self::KeyEncipherment,
+            KeyUsage::DataEncipherment => Self::DataEncipherment,
+            KeyUsage::KeyAgreement => Self::KeyAgreement,
+            KeyUsage::KeyCertSign => Self::KeyCertSign,
+            KeyUsage::CrlSign => Self::CrlSign,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum ExtendedKeyUsage {
+    ServerAuth,
+    ClientAuth,
+    CodeSigning,
+    EmailProtection,
+    TimeStamping,
+    OcspSigning,
+}
+
+impl From<ExtendedKeyUsage> for rcgen::ExtendedKeyUsagePurpose {
+    fn from(extended_key_usage: ExtendedKeyUsage) -> Self {
+        match extended_key_usage {
+            ExtendedKeyUsage::ServerAuth => Self::ServerAuth,
+            ExtendedKeyUsage::ClientAuth => Self::ClientAuth,
+            ExtendedKeyUsage::CodeSigning => Self::CodeSigning,
+            ExtendedKeyUsage::EmailProtection => Self::EmailProtection,
+            ExtendedKeyUsage::TimeStamping => Self::TimeStamping,
+            ExtendedKeyUsage::OcspSigning => Self::OcspSigning,
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 pub enum X509Command {
     Create(Command<CreateCommand>),
@@ -81,11 +161,31 @@ impl Command<CreateCommand> {
         distinguished_name.push(DnType::CommonName, self.inner.common_name.clone());
         params.distinguished_name = distinguished_name;
 
+        // subject alternative names
+        params.subject_alt_names = self.inner.subject_alt_names;
+
         //  key usages
-        params.key_usages = vec![rcgen::KeyUsagePurpose::DigitalSignature];
+        params.key_usages = if !self.inner.key_usages.is_empty() {
+            self.inner.key_usages.into_iter().map(Into::into).collect()
+        } else if self.inner.is_ca {
+            vec![
+                rcgen::KeyUsagePurpose::KeyCertSign,
+                rcgen::KeyUsagePurpose::CrlSign,
+            ]
+        } else {
+            vec![rcgen::KeyUsagePurpose::DigitalSignature]
+        };
 
         // extended key usages
-        params.extended_key_usages = vec![rcgen::ExtendedKeyUsagePurpose::ClientAuth];
+        params.extended_key_usages = if !self.inner.extended_key_usages.is_empty() {
+            self.inner
+                .extended_key_usages
+                .into_iter()
+                .map(Into::into)
+                .collect()
+        } else {
+            vec![rcgen::ExtendedKeyUsagePurpose::ClientAuth]
+        };
 
         // validity period
         let start = parse_date(&self.inner.start_date)?;
@@ -98,105 +198,16 @@ impl Command<CreateCommand> {
 
         // signed by or self signed
         let cert = if let Some(signer_name) = self.inner.signer {
-            if let Some(certificate_authorities) = global_options.certificate_authorities {
-                if let Some(signer) = certificate_authorities.get(&signer_name) {
-                    let signer_key = {
-                        let path = Path::new(&signer.private_key);
-                        if !path.exists() {
-                            return Err(Error::NonExistingPath {
-                                path: path.to_path_buf(),
-                                source: std::io::Error::new(
-                                    std::io::ErrorKind::NotFound,
-                                    "File not found",
-                                ),
-                            });
-                        }
-                        KeyPair::from_pem(
-                            &fs::read_to_string(path).context(NonExistingPathSnafu { path })?,
-                        )
-                        .context(CertParamsCreationSnafu)?
-                    };
-
-                    let signer_cert_pem = {
-                        let path = Path::new(&signer.certificate);
-                        if !path.exists() {
-                            return Err(Error::NonExistingPath {
-                                path: path.to_path_buf(),
-                                source: std::io::Error::new(
-                                    std::io::ErrorKind::NotFound,
-                                    "File not found",
-                                ),
-                            });
-                        }
-                        fs::read_to_string(path).context(NonExistingPathSnafu { path })?
-                    };
-
-                    let signer_cert = CertificateParams::from_ca_cert_pem(&signer_cert_pem)
-                        .context(CertParamsCreationSnafu)?
-                        .self_signed(&signer_key)
-                        .context(CertificateCreationSnafu)?;
-                    params
-                        .signed_by(&key_pair, &signer_cert, &signer_key)
-                        .context(CertificateCreationSnafu)?
-                } else {
-                    let mut error = StyledStr::new();
-                    error.push_str(Some(Style::Error), "error: ".to_string());
-                    error.push_str(None, "Config file field ".to_string());
-                    error.push_str(None, "'".to_string());
-                    error.push_str(
-                        Some(Style::Warning),
-                        format!("certificate_authorities.{signer_name}").to_string(),
-                    );
-                    error.push_str(None, "'".to_string());
-                    error.push_str(
-                        None,
-                        " is unset or null, but is required by the --signer option.".to_string(),
-                    );
-                    error.print_data_err();
-                }
-            } else {
-                let mut error = StyledStr::new();
-                error.push_str(Some(Style::Error), "error: ".to_string());
-                error.push_str(None, "Config file field ".to_string());
-                error.push_str(None, "'".to_string());
-                error.push_str(Some(Style::Warning), "certificate_authorities".to_string());
-                error.push_str(None, "'".to_string());
-                error.push_str(
-                    None,
-                    " is unset or null, but is required by the --signer option.".to_string(),
-                );
-                error.print_data_err();
-            }
+            let (signer_key, signer_cert) = config_signer(&global_options, &signer_name)?;
+
+            params
+                .signed_by(&key_pair, &signer_cert, &signer_key)
+                .context(CertificateCreationSnafu)?
         } else if let (Some(signer_key_path), Some(signer_cert_path)) =
             (self.inner.signer_key, self.inner.signer_cert)
         {
-            let signer_key = {
-                let path = Path::new(&signer_key_path);
-                if !path.exists() {
-                    return Err(Error::NonExistingPath {
-                        path: path.to_path_buf(),
-                        source: std::io::Error::new(std::io::ErrorKind::NotFound, "File not found"),
-                    });
-                }
-                KeyPair::from_pem(&fs::read_to_string(path).context(NonExistingPathSnafu { path })?)
-                    .context(CertParamsCreationSnafu)?
-            };
-
-            let signer_cert_pem = {
-                let path = Path::new(&signer_cert_path);
-                if !path.exists() {
-                    return Err(Error::NonExistingPath {
-                        path: path.to_path_buf(),
-                        source: std::io::Error::new(std::io::ErrorKind::NotFound, "File not found"),
-                    });
-                }
-                fs::read_to_string(path).context(NonExistingPathSnafu { path })?
-            };
-
-            let signer_cert = CertificateParams::from_ca_cert_pem(&signer_cert_pem)
-                .context(CertParamsCreationSnafu)?
-                .self_signed(&signer_key)
-                .context(CertificateCreationSnafu)?;
+            let (signer_key, signer_cert) = signer_from_paths(&signer_key_path, &signer_cert_path)?;
+
             params
                 .signed_by(&key_pair, &signer_cert, &signer_key)
                 .context(CertificateCreationSnafu)?
@@ -206,31 +217,234 @@ impl Command<CreateCommand> {
                 .context(CertificateCreationSnafu)?
         };
 
-        let cert_pem = cert.pem();
-        let key_pem = key_pair.serialize_pem();
-
-        let out_dir = self
-            .inner
-            .out
-            .unwrap_or_else(|| env::current_dir().unwrap());
-        fs::create_dir_all(&out_dir).unwrap();
-        let cert_filename = format!("{}-certificate.pem", self.inner.common_name);
-        let key_filename = format!("{}-private-key.pem", self.inner.common_name);
-        fs::write(out_dir.join(cert_filename.clone()), cert_pem).unwrap();
-        fs::write(out_dir.join(key_filename.clone()), key_pem).unwrap();
+        let (certificate, private_key) =
+            write_certificate_and_key(self.inner.out, &self.inner.common_name, &cert, &key_pair)?;
 
         print_json!(&json!({
-            "certificate": out_dir.join(cert_filename),
-            "private_key": out_dir.join(key_filename)
+            "certificate": certificate,
+            "private_key": private_key
         }));
 
         Ok(())
     }
 }
 
-fn parse_date(date_str: &str) -> Result<OffsetDateTime, Error> {
+/// Create a self-signed certificate authority valid from `not_before` to `not_after`.
+pub(crate) fn self_signed_ca(
+    common_name: &str,
+    not_before: OffsetDateTime,
+    not_after: OffsetDateTime,
+) -> Result<(Certificate, KeyPair), Error> {
+    let mut params = CertificateParams::default();
+    params.is_ca = IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    params.use_authority_key_identifier_extension = true;
+
+    let mut distinguished_name = DistinguishedName::new();
+    distinguished_name.push(DnType::CommonName, common_name);
+    params.distinguished_name = distinguished_name;
+
+    params.key_usages = vec![
+        rcgen::KeyUsagePurpose::KeyCertSign,
+        rcgen::KeyUsagePurpose::CrlSign,
+    ];
+    params.not_before = not_before;
+    params.not_after = not_after;
+
+    let key_pair = KeyPair::generate().context(CertParamsCreationSnafu)?;
+    let cert = params
+        .self_signed(&key_pair)
+        .context(CertificateCreationSnafu)?;
+
+    Ok((cert, key_pair))
+}
+
+/// Create a client certificate for a device, with the device identifier as its common name,
+/// signed by a certificate authority.
+pub(crate) fn device_certificate(
+    device_identifier: &str,
+    not_before: OffsetDateTime,
+    not_after: OffsetDateTime,
+    signer_key: &KeyPair,
+    signer_cert: &Certificate,
+) -> Result<(Certificate, KeyPair), Error> {
+    let mut params = CertificateParams::default();
+    params.is_ca = IsCa::NoCa;
+    params.use_authority_key_identifier_extension = true;
+
+    let mut distinguished_name = DistinguishedName::new();
+    distinguished_name.push(DnType::CommonName, device_identifier);
+    params.distinguished_name = distinguished_name;
+
+    params.key_usages = vec![rcgen::KeyUsagePurpose::DigitalSignature];
+    params.extended_key_usages = vec![rcgen::ExtendedKeyUsagePurpose::ClientAuth];
+
+    params.not_before = not_before;
+    params.not_after = not_after;
+
+    let key_pair = KeyPair::generate().context(CertParamsCreationSnafu)?;
+    let cert = params
+        .signed_by(&key_pair, signer_cert, signer_key)
+        .context(CertificateCreationSnafu)?;
+
+    Ok((cert, key_pair))
+}
+
+/// Create the certificate that proves possession of a CA's private key when registering it:
+/// a certificate whose common name is the verification code from the Peridio API, signed by
+/// the CA.
+pub(crate) fn verification_certificate(
+    verification_code: &str,
+    signer_key: &KeyPair,
+    signer_cert: &Certificate,
+) -> Result<Certificate, Error> {
+    let mut params = CertificateParams::default();
+
+    let mut distinguished_name = DistinguishedName::new();
+    distinguished_name.push(DnType::CommonName, verification_code);
+    params.distinguished_name = distinguished_name;
+
+    let key_pair = KeyPair::generate().context(CertParamsCreationSnafu)?;
+
+    params
+        .signed_by(&key_pair, signer_cert, signer_key)
+        .context(CertificateCreationSnafu)
+}
+
+/// Load the private key and certificate of a certificate authority from the CLI config.
+pub(crate) fn config_signer(
+    global_options: &GlobalOptions,
+    signer_name: &str,
+) -> Result<(KeyPair, Certificate), Error> {
+    if let Some(certificate_authorities) = &global_options.certificate_authorities {
+        if let Some(signer) = certificate_authorities.get(signer_name) {
+            signer_from_paths(
+                Path::new(&signer.private_key),
+                Path::new(&signer.certificate),
+            )
+        } else {
+            let mut error = StyledStr::new();
+            error.push_str(Some(Style::Error), "error: ".to_string());
+            error.push_str(None, "Config file field ".to_string());
+            error.push_str(None, "'".to_string());
+            error.push_str(
+                Some(Style::Warning),
+                format!("certificate_authorities.{signer_name}").to_string(),
+            );
+            error.push_str(None, "'".to_string());
+            error.push_str(
+                None,
+                " is unset or null, but is required by the --signer option.".to_string(),
+            );
+            error.print_data_err();
+        }
+    } else {
+        let mut error = StyledStr::new();
+        error.push_str(Some(Style::Error), "error: ".to_string());
+        error.push_str(None, "Config file field ".to_string());
+        error.push_str(None, "'".to_string());
+        error.push_str(Some(Style::Warning), "certificate_authorities".to_string());
+        error.push_str(None, "'".to_string());
+        error.push_str(
+            None,
+            " is unset or null, but is required by the --signer option.".to_string(),
+        );
+        error.print_data_err();
+    }
+}
+
+/// Load the private key and certificate of a certificate authority from PEM files.
+pub(crate) fn signer_from_paths(
+    signer_key_path: &Path,
+    signer_cert_path: &Path,
+) -> Result<(KeyPair, Certificate), Error> {
+    let signer_key = {
+        let path = signer_key_path;
+        if !path.exists() {
+            return Err(Error::NonExistingPath {
+                path: path.to_path_buf(),
+                source: std::io::Error::new(std::io::ErrorKind::NotFound, "File not found"),
+            });
+        }
+        KeyPair::from_pem(&fs::read_to_string(path).context(NonExistingPathSnafu { path })?)
+            .context(CertParamsCreationSnafu)?
+    };
+
+    let signer_cert_pem = {
+        let path = signer_cert_path;
+        if !path.exists() {
+            return Err(Error::NonExistingPath {
+                path: path.to_path_buf(),
+                source: std::io::Error::new(std::io::ErrorKind::NotFound, "File not found"),
+            });
+        }
+        fs::read_to_string(path).context(NonExistingPathSnafu { path })?
+    };
+
+    let signer_cert = CertificateParams::from_ca_cert_pem(&signer_cert_pem)
+        .context(CertParamsCreationSnafu)?
+        .self_signed(&signer_key)
+        .context(CertificateCreationSnafu)?;
+
+    Ok((signer_key, signer_cert))
+}
+
+/// Write `{name}-certificate.pem` and `{name}-private-key.pem` to `out` (defaults to the current
+/// working directory), returning their paths.
+///
+/// The private key is only readable by its owner on Unix, and an existing private key is never
+/// overwritten.
+pub(crate) fn write_certificate_and_key(
+    out: Option<PathBuf>,
+    name: &str,
+    cert: &Certificate,
+    key_pair: &KeyPair,
+) -> Result<(PathBuf, PathBuf), Error> {
+    let cert_pem = cert.pem();
+    let key_pem = key_pair.serialize_pem();
+
+    let out_dir = match out {
+        Some(out) => out,
+        None => env::current_dir().context(FileSnafu)?,
+    };
+    fs::create_dir_all(&out_dir).context(FileWriteSnafu { path: &out_dir })?;
+    let cert_path = out_dir.join(format!("{name}-certificate.pem"));
+    let key_path = out_dir.join(format!("{name}-private-key.pem"));
+    // the key goes first, so an existing key is never left next to a certificate it doesn't match
+    write_private_key(&key_path, &key_pem).context(FileWriteSnafu { path: &key_path })?;
+    fs::write(&cert_path, cert_pem).context(FileWriteSnafu { path: &cert_path })?;
+
+    Ok((cert_path, key_path))
+}
+
+/// Parse either a bare date (YYYY-MM-DD, at midnight UTC) or a full RFC 3339 datetime.
+pub(crate) fn parse_date(date_str: &str) -> Result<OffsetDateTime, Error> {
+    if date_str.contains(['T', 't', ' ']) {
+        return OffsetDateTime::parse(date_str, &Rfc3339).context(DateParseSnafu);
+    }
+
     let format = format_description!("[year]-[month]-[day]");
     time::Date::parse(date_str, &format)
         .map(|date| date.with_time(time::Time::MIDNIGHT).assume_utc())
-        .map_err(|e| Error::DateParse { source: e })
+        .context(DateParseSnafu)
+}
+
+fn parse_san(value: &str) -> Result<SanType, String> {
+    let (kind, name) = value
+        .split_once(':')
+        .ok_or_else(|| "expected <type>:<value>, e.g. dns:device.example.com".to_string())?;
+
+    let ia5 = |name: &str| Ia5String::try_from(name).map_err(|e| e.to_string());
+
+    match kind {
+        "dns" => Ok(SanType::DnsName(ia5(name)?)),
+        "ip" => name
+            .parse()
+            .map(SanType::IpAddress)
+            .map_err(|e| format!("invalid IP address '{name}': {e}")),
+        "email" => Ok(SanType::Rfc822Name(ia5(name)?)),
+        "uri" => Ok(SanType::URI(ia5(name)?)),
+        _ => Err(format!(
+            "unrecognized SAN type '{kind}', expected one of: dns, ip, email, uri"
+        )),
+    }
 }
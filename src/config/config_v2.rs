@@ -96,6 +96,12 @@ impl Deref for SigningKeyPairsV2 {
     }
 }
 
+impl DerefMut for SigningKeyPairsV2 {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CertificateAuthorityV2 {
     pub private_key: String,
@@ -113,6 +119,12 @@ impl Deref for CertificateAuthoritiesV2 {
     }
 }
 
+impl DerefMut for CertificateAuthoritiesV2 {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ConfigV2 {
     pub version: u8,
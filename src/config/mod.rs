@@ -3,10 +3,17 @@ pub(crate) mod config_v2;
 
 use crate::config::config_v2::ConfigV2;
 use crate::config::config_v2::ProfileV2;
-use crate::utils::{Style, StyledStr};
+use crate::utils::{expand_path, Style, StyledStr};
+use crate::{ConfigParseSnafu, ConfigReadSnafu, NonExistingPathSnafu};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, path::PathBuf};
+use snafu::ResultExt;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
 
 use self::config_v1::ConfigV1;
 
@@ -38,34 +45,51 @@ impl Config {
         }
     }
 
-    pub fn parse(config_directory: &Option<String>) -> Option<ConfigV2> {
-        // get directory
-        let mut config_dir_path = if let Some(config_dir) = config_directory {
+    /// Resolve the config directory: `config_directory` when given, otherwise the default
+    /// config directory.
+    ///
+    /// Returns `Ok(None)` when there is no home directory to derive the default from.
+    pub fn directory(config_directory: &Option<String>) -> Result<Option<PathBuf>, crate::Error> {
+        if let Some(config_dir) = config_directory {
             let config_dir_path = PathBuf::from(config_dir);
 
             if config_dir_path.exists() {
-                // use this config
-                config_dir_path
+                Ok(Some(config_dir_path))
             } else {
-                panic!("The provided config directory is invalid");
+                Err(io::Error::from(io::ErrorKind::NotFound)).context(NonExistingPathSnafu {
+                    path: config_dir_path,
+                })
             }
-        } else if let Some(proj_dirs) = ProjectDirs::from("", "", "peridio") {
-            let cache_dir = proj_dirs.config_dir();
-
-            fs::create_dir_all(cache_dir).unwrap();
-
-            cache_dir.to_path_buf()
         } else {
-            panic!("We can't determine your config path")
+            Ok(ProjectDirs::from("", "", "peridio")
+                .map(|proj_dirs| proj_dirs.config_dir().to_path_buf()))
+        }
+    }
+
+    /// Read the config and credentials files from `config_directory`, or from the default
+    /// config directory when none is given.
+    ///
+    /// Returns `Ok(None)` when there is no config file to read.
+    pub fn parse(config_directory: &Option<String>) -> Result<Option<ConfigV2>, crate::Error> {
+        // reading never creates the directory, commands that write the config do that
+        let Some(mut config_dir_path) = Self::directory(config_directory)? else {
+            // without a home directory there is no default config to read
+            return Ok(None);
         };
 
         // get credentials
         config_dir_path.push("credentials.json");
 
         let credentials: HashMap<String, Credential> = if config_dir_path.exists() {
+            warn_if_readable_by_others(&config_dir_path);
+
             let credentials_file =
-                fs::read_to_string(&config_dir_path).expect("Cannot read credentials file");
-            serde_json::from_str(&credentials_file).expect("Cannot read credential file")
+                fs::read_to_string(&config_dir_path).context(ConfigReadSnafu {
+                    path: &config_dir_path,
+                })?;
+            serde_json::from_str(&credentials_file).context(ConfigParseSnafu {
+                path: &config_dir_path,
+            })?
         } else {
             HashMap::new()
         };
@@ -75,8 +99,9 @@ impl Config {
         config_dir_path.push("config.json");
 
         if config_dir_path.exists() {
-            let config_file =
-                fs::read_to_string(&config_dir_path).expect("Cannot read config file");
+            let config_file = fs::read_to_string(&config_dir_path).context(ConfigReadSnafu {
+                path: &config_dir_path,
+            })?;
 
             if serde_json::from_str::<ConfigV1>(&config_file).is_ok() {
                 let mut error = StyledStr::new();
@@ -87,7 +112,9 @@ impl Config {
             }
 
             let mut config: ConfigV2 =
-                serde_json::from_str(&config_file).expect("Cannot read config file");
+                serde_json::from_str(&config_file).context(ConfigParseSnafu {
+                    path: &config_dir_path,
+                })?;
 
             for (profile_name, profile) in config.profiles.iter_mut() {
                 if let Some(credential) = credentials.get(profile_name) {
@@ -95,9 +122,105 @@ impl Config {
                 }
             }
 
-            Some(config)
+            // paths in the config file may be home-relative or reference env vars
+            for profile in config.profiles.values_mut() {
+                if let Some(ca_path) = profile.ca_path.as_mut() {
+                    *ca_path = expand_path(ca_path);
+                }
+            }
+
+            if let Some(signing_key_pairs) = config.signing_key_pairs.as_mut() {
+                for key_pair in signing_key_pairs.values_mut() {
+                    key_pair.signing_key_private_path =
+                        expand_path(&key_pair.signing_key_private_path);
+                }
+            }
+
+            if let Some(certificate_authorities) = config.certificate_authorities.as_mut() {
+                for certificate_authority in certificate_authorities.values_mut() {
+                    certificate_authority.private_key =
+                        expand_path(&certificate_authority.private_key);
+                    certificate_authority.certificate =
+                        expand_path(&certificate_authority.certificate);
+                }
+            }
+
+            Ok(Some(config))
         } else {
-            None
+            Ok(None)
+        }
+    }
+}
+
+/// Write `contents` to `path` without ever leaving a partially written file behind.
+///
+/// The data is written to a temporary file in the same directory and then renamed over
+/// `path`, so an interrupted write keeps the original file intact. When `path` already
+/// exists its permissions are carried over to the new file, otherwise it is created with
+/// mode 0600 on Unix.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?
+        .to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+
+    let result = (|| {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+
+        // new files may hold API keys, so only the owner gets access by default
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options.open(&tmp_path)?;
+
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+
+        file.write_all(contents)?;
+        file.sync_all()?;
+
+        fs::rename(&tmp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    result
+}
+
+/// Print a warning if the file at `path` can be read by its group or other users.
+fn warn_if_readable_by_others(path: &Path) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if let Ok(metadata) = fs::metadata(path) {
+            if metadata.permissions().mode() & 0o044 != 0 {
+                let mut warning = StyledStr::new();
+                warning.push_str(Some(Style::Warning), "warning: ".to_string());
+                warning.push_str(
+                    None,
+                    format!(
+                        "{} is readable by other users. Restrict it by running:\r\n",
+                        path.display()
+                    ),
+                );
+                warning.push_str(
+                    Some(Style::Success),
+                    format!("\tchmod 600 {}", path.display()),
+                );
+                let _ = warning.print_err();
+            }
         }
     }
+
+    #[cfg(not(unix))]
+    let _ = path;
 }
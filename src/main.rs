@@ -11,19 +11,27 @@ use std::{
 
 use clap::Parser;
 use config::Config;
-use snafu::Snafu;
+use snafu::{ResultExt, Snafu};
 
 use crate::config::config_v2::{CertificateAuthoritiesV2, SigningKeyPairsV2};
-use crate::utils::{Style, StyledStr};
+use crate::utils::{ApiFailure, ColorMode, OutputFormat, Style, StyledStr};
 
 #[macro_export]
 #[allow(clippy::crate_in_macro_def)]
 macro_rules! print_json {
     ($v:expr) => {
-        println!(
-            "{}",
-            serde_json::to_string($v).context(crate::JsonSerializationSnafu)?
-        )
+        crate::utils::print_output($v)?
+    };
+}
+
+/// Print an informational message to stderr, unless `--quiet` was given.
+#[macro_export]
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! print_status {
+    ($($arg:tt)*) => {
+        if !crate::utils::quiet() {
+            eprintln!($($arg)*)
+        }
     };
 }
 
@@ -33,12 +41,50 @@ pub enum Error {
     #[snafu(display("{}", source))]
     Api { source: peridio_sdk::api::Error },
 
+    #[snafu(display("Received an empty response from the API for '{}'", context))]
+    EmptyResponse { context: String },
+
+    #[snafu(display("The API response for '{}' has no '{}' field", context, field))]
+    MissingField { context: String, field: String },
+
     #[snafu(display("Unable to serialize to JSON {}", source))]
     JsonSerialization { source: serde_json::Error },
 
+    #[snafu(display("Unable to serialize to YAML {}", source))]
+    YamlSerialization { source: serde_yaml::Error },
+
     #[snafu(display("Unable to open file {}", source))]
     File { source: io::Error },
 
+    #[snafu(display("Unable to write {}: {}", path.display(), source))]
+    FileWrite {
+        path: path::PathBuf,
+        source: io::Error,
+    },
+
+    #[snafu(display("Unable to read {}: {}", path.display(), source))]
+    ConfigRead {
+        path: path::PathBuf,
+        source: io::Error,
+    },
+
+    #[snafu(display("Unable to parse {}: {}", path.display(), source))]
+    ConfigParse {
+        path: path::PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[snafu(display("Unable to upgrade {}: {}", path.display(), reason))]
+    ConfigUpgrade { path: path::PathBuf, reason: String },
+
+    #[snafu(display(
+        "Unable to determine your config directory, provide one with --config-directory."
+    ))]
+    ConfigDirectoryUnknown,
+
+    #[snafu(display("Signing key pair '{}' already exists in {}.", name, path.display()))]
+    SigningKeyPairExists { name: String, path: path::PathBuf },
+
     #[snafu(display("{:?}", path))]
     NonExistingPath {
         path: path::PathBuf,
@@ -51,8 +97,121 @@ pub enum Error {
     #[snafu(display("Failed to create certificate: {}", source))]
     CertificateCreation { source: rcgen::Error },
 
-    #[snafu(display("Failed to parse date: {}", source))]
+    #[snafu(display(
+        "Failed to parse date, expected YYYY-MM-DD or an RFC 3339 datetime: {}",
+        source
+    ))]
     DateParse { source: time::error::Parse },
+
+    #[snafu(display("Invalid signing key: {}", reason))]
+    SigningKey { reason: String },
+
+    #[snafu(display("unsupported key algorithm: {}", oid))]
+    UnsupportedKeyAlgorithm { oid: String },
+
+    #[snafu(display("Unable to parse {}: {}", path.display(), source))]
+    CsvParse {
+        path: path::PathBuf,
+        source: csv::Error,
+    },
+
+    #[snafu(display(
+        "{}: row {} repeats the identifier '{}' of row {}",
+        path.display(),
+        row,
+        identifier,
+        first_row
+    ))]
+    DuplicateDeviceIdentifier {
+        path: path::PathBuf,
+        identifier: String,
+        row: usize,
+        first_row: usize,
+    },
+
+    #[snafu(display("{} of {} devices could not be created", failed, total))]
+    BulkCreate { failed: usize, total: usize },
+
+    #[snafu(display("Unable to promote release: {}", reason))]
+    ReleasePromotion { reason: String },
+
+    #[snafu(display(
+        "Counted {} results in {} pages, but more are available. Use --max-pages to fetch more.",
+        count,
+        max_pages
+    ))]
+    IncompleteCount { count: usize, max_pages: u32 },
+
+    #[snafu(display("Unknown field(s): {}. Available fields: {}", unknown, available))]
+    UnknownFields { unknown: String, available: String },
+
+    #[snafu(display("Local content does not match binary {}", prn))]
+    BinaryMismatch { prn: String },
+
+    #[snafu(display(
+        "Timed out after {}s waiting for binary {} to become signable, last observed state: {}",
+        waited,
+        prn,
+        state
+    ))]
+    SignableTimeout {
+        prn: String,
+        waited: u64,
+        state: String,
+    },
+
+    #[snafu(display("Invalid base URL '{}': {}", base_url, reason))]
+    InvalidBaseUrl { base_url: String, reason: String },
+
+    #[snafu(display("Unable to upgrade: {}", reason))]
+    Upgrade { reason: String },
+
+    #[snafu(display("Unable to upload binary part {}: {}", index, reason))]
+    BinaryPartUpload { index: u64, reason: String },
+
+    #[snafu(display("Unable to load {}: {}", path.display(), source))]
+    EnvFile {
+        path: path::PathBuf,
+        source: dotenvy::Error,
+    },
+}
+
+impl Error {
+    /// A stable identifier for the kind of error, for tools that parse JSON error output.
+    fn kind(&self) -> &'static str {
+        match self {
+            Error::Api { .. } => "api",
+            Error::EmptyResponse { .. } => "empty_response",
+            Error::MissingField { .. } => "missing_field",
+            Error::JsonSerialization { .. } => "json_serialization",
+            Error::YamlSerialization { .. } => "yaml_serialization",
+            Error::File { .. } => "file",
+            Error::FileWrite { .. } => "file_write",
+            Error::ConfigRead { .. } => "config_read",
+            Error::ConfigParse { .. } => "config_parse",
+            Error::ConfigUpgrade { .. } => "config_upgrade",
+            Error::ConfigDirectoryUnknown => "config_directory_unknown",
+            Error::SigningKeyPairExists { .. } => "signing_key_pair_exists",
+            Error::NonExistingPath { .. } => "non_existing_path",
+            Error::CertParamsCreation { .. } => "cert_params_creation",
+            Error::CertificateCreation { .. } => "certificate_creation",
+            Error::DateParse { .. } => "date_parse",
+            Error::SigningKey { .. } => "signing_key",
+            Error::UnsupportedKeyAlgorithm { .. } => "unsupported_key_algorithm",
+            Error::CsvParse { .. } => "csv_parse",
+            Error::DuplicateDeviceIdentifier { .. } => "duplicate_device_identifier",
+            Error::BulkCreate { .. } => "bulk_create",
+            Error::ReleasePromotion { .. } => "release_promotion",
+            Error::IncompleteCount { .. } => "incomplete_count",
+            Error::UnknownFields { .. } => "unknown_fields",
+            Error::BinaryMismatch { .. } => "binary_mismatch",
+            Error::SignableTimeout { .. } => "signable_timeout",
+            Error::InvalidBaseUrl { .. } => "invalid_base_url",
+            Error::Upgrade { .. } => "upgrade",
+            Error::BinaryPartUpload { .. } => "binary_part_upload",
+            Error::EnvFile { .. } => "env_file",
+        }
+    }
 }
 
 impl fmt::Debug for Error {
@@ -88,14 +247,37 @@ pub struct GlobalOptions {
     #[arg(long, env = "PERIDIO_PROFILE", short = 'p')]
     profile: Option<String>,
 
-    #[arg(
-        long,
-        env = "PERIDIO_CONFIG_DIRECTORY",
-        short = 'd',
-        requires = "profile"
-    )]
+    #[arg(long, env = "PERIDIO_CONFIG_DIRECTORY", short = 'd')]
     config_directory: Option<String>,
 
+    /// Load environment variables from a dotenv file before reading the PERIDIO_* variables.
+    ///
+    /// Variables already set in the environment take precedence over the file.
+    #[arg(long, value_name = "PATH")]
+    env_file: Option<PathBuf>,
+
+    /// The format to print results in [default: json]
+    ///
+    /// When json, json-pretty or jsonl is given explicitly, errors are also printed to stderr as JSON.
+    #[arg(long, env = "PERIDIO_OUTPUT", short = 'O', value_enum)]
+    output: Option<OutputFormat>,
+
+    /// Suppress informational messages on stderr.
+    ///
+    /// Errors are still printed, as is the command's result on stdout.
+    #[arg(long, short = 'q')]
+    quiet: bool,
+
+    /// When to color messages on stderr.
+    ///
+    /// With auto, color is used when stderr is a terminal and the NO_COLOR environment variable is not set.
+    #[arg(long, env = "PERIDIO_COLOR", value_enum, default_value_t = ColorMode::Auto)]
+    color: ColorMode,
+
+    /// The profile whose settings were applied, if any.
+    #[clap(skip)]
+    loaded_profile: Option<String>,
+
     #[clap(skip)]
     signing_key_pairs: Option<SigningKeyPairsV2>,
 
@@ -105,6 +287,14 @@ pub struct GlobalOptions {
 
 impl Program {
     async fn run(mut self) -> Result<(), Error> {
+        utils::set_output_format(self.global_options.output);
+        utils::set_json_errors(matches!(
+            self.global_options.output,
+            Some(OutputFormat::Json | OutputFormat::JsonPretty | OutputFormat::Jsonl)
+        ));
+        utils::set_color_mode(self.global_options.color);
+        utils::set_quiet(self.global_options.quiet);
+
         if let Some(path) = &self.global_options.ca_path {
             if !path.exists() {
                 return Err(Error::NonExistingPath {
@@ -117,12 +307,16 @@ impl Program {
         // parse config files if profile config is provided
 
         match self.command {
-            Command::CliCommand(api::CliCommands::Config(_)) => (),
+            Command::CliCommand(api::CliCommands::Config(_))
+            | Command::CliCommand(api::CliCommands::Completions(_))
+            | Command::CliCommand(api::CliCommands::Version(_)) => (),
             _ => {
-                if let Some(config) = Config::parse(&self.global_options.config_directory) {
+                if let Some(config) = Config::parse(&self.global_options.config_directory)? {
                     if let Some(profile_name) = &self.global_options.profile {
                         if let Ok(profile) = Config::get_profile(&config, profile_name) {
                             // profile was provided
+                            self.global_options.loaded_profile = Some(profile_name.clone());
+
                             if self.global_options.api_key.is_none() {
                                 if let Some(api_key) = profile.api_key {
                                     self.global_options.api_key = Some(api_key);
@@ -151,10 +345,31 @@ impl Program {
 
                     self.global_options.signing_key_pairs = config.signing_key_pairs;
                     self.global_options.certificate_authorities = config.certificate_authorities;
+                } else if let Some(profile_name) = &self.global_options.profile {
+                    let location = match Config::directory(&self.global_options.config_directory)? {
+                        Some(config_dir) => {
+                            format!("at {}", config_dir.join("config.json").display())
+                        }
+                        None => "because the config directory can't be determined".to_string(),
+                    };
+
+                    let mut warning = StyledStr::new();
+                    warning.push_str(Some(Style::Warning), "warning: ".to_string());
+                    warning.push_str(None, "Profile '".to_string());
+                    warning.push_str(Some(Style::Warning), profile_name.to_string());
+                    warning.push_str(
+                        None,
+                        format!("' was not loaded, no config file was found {location}."),
+                    );
+                    let _ = warning.print_err();
                 }
             }
         };
 
+        if let Some(base_url) = &self.global_options.base_url {
+            self.global_options.base_url = Some(utils::normalize_base_url(base_url)?);
+        }
+
         match self.command {
             Command::CliCommand(cmd) => cmd.run(self.global_options).await?,
         };
@@ -170,15 +385,99 @@ enum Command {
     CliCommand(api::CliCommands),
 }
 
+/// Loads the file given to `--env-file` into the environment. This has to happen before clap
+/// parses the arguments, as that's when the `PERIDIO_*` variables are read.
+fn load_env_file() -> Result<(), Error> {
+    let mut args = std::env::args_os().skip(1);
+
+    while let Some(arg) = args.next() {
+        let path = if arg == "--env-file" {
+            // a missing value is reported by clap
+            args.next()
+        } else if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--env-file=")) {
+            Some(path.into())
+        } else {
+            continue;
+        };
+
+        if let Some(path) = path.map(PathBuf::from) {
+            dotenvy::from_path(&path).context(EnvFileSnafu { path })?;
+        }
+
+        break;
+    }
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> ExitCode {
-    match Program::parse().run().await {
+    let result = match load_env_file() {
+        Ok(()) => Program::parse().run().await,
+        error => error,
+    };
+
+    match result {
+        Err(error) if utils::json_errors() => {
+            let message = match &error {
+                Error::NonExistingPath { path, source: _ } => {
+                    format!("Path does not exist: {}", path.display())
+                }
+                error => error.to_string(),
+            };
+
+            eprintln!(
+                "{}",
+                serde_json::json!({
+                    "error": {
+                        "kind": error.kind(),
+                        "message": message,
+                    }
+                })
+            );
+
+            match error {
+                // matches the exit status of the human readable output
+                Error::EmptyResponse { .. }
+                | Error::NonExistingPath { .. }
+                | Error::ConfigDirectoryUnknown
+                | Error::SigningKeyPairExists { .. } => ExitCode::from(65),
+                _ => ExitCode::FAILURE,
+            }
+        }
         Err(error) => {
             match error {
+                Error::Api { source } if ApiFailure::of(&source) == ApiFailure::Tls => {
+                    let mut error = StyledStr::new();
+                    error.push_str(Some(Style::Error), "error: ".to_string());
+                    error.push_str(None, format!("TLS handshake failed: {source}\r\n"));
+                    error.push_str(
+                        None,
+                        "The server's certificate could not be verified. If you use a self-hosted Peridio Cloud, point the CLI at its CA bundle with:\r\n"
+                            .to_string(),
+                    );
+                    error.push_str(
+                        Some(Style::Success),
+                        "\t--ca-path <CA_PATH> or PERIDIO_CA_PATH".to_string(),
+                    );
+                    let _ = error.print_err();
+                }
+
                 Error::Api { source } => {
                     eprintln!("{source}")
                 }
 
+                Error::EmptyResponse { context } => {
+                    let mut error = StyledStr::new();
+                    error.push_str(Some(Style::Error), "error: ".to_string());
+                    error.push_str(
+                        None,
+                        "Received an empty response from the API for ".to_string(),
+                    );
+                    error.push_str(Some(Style::Warning), context);
+                    error.print_data_err();
+                }
+
                 Error::NonExistingPath { path, source: _ } => {
                     let mut error = StyledStr::new();
                     error.push_str(Some(Style::Error), "error: ".to_string());
@@ -187,6 +486,22 @@ async fn main() -> ExitCode {
                     error.print_data_err();
                 }
 
+                Error::ConfigDirectoryUnknown => {
+                    let mut error = StyledStr::new();
+                    error.push_str(Some(Style::Error), "error: ".to_string());
+                    error.push_str(None, Error::ConfigDirectoryUnknown.to_string());
+                    error.print_data_err();
+                }
+
+                Error::SigningKeyPairExists { name, path } => {
+                    let mut error = StyledStr::new();
+                    error.push_str(Some(Style::Error), "error: ".to_string());
+                    error.push_str(None, "Signing key pair '".to_string());
+                    error.push_str(Some(Style::Warning), name);
+                    error.push_str(None, format!("' already exists in {}.", path.display()));
+                    error.print_data_err();
+                }
+
                 error => eprintln!("Error: {error}"),
             }
 
@@ -1,9 +1,234 @@
+pub mod retry;
+pub mod signing;
+
 use clap::error::{ContextKind, ContextValue, ErrorKind};
+use serde::Serialize;
 use serde_json::{Map, Value};
-use std::io::Write;
+use snafu::{ResultExt, Snafu};
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::OnceLock;
 use termcolor::WriteColor;
 use uuid::Uuid;
 
+use crate::{Error, JsonSerializationSnafu, YamlSerializationSnafu};
+
+static OUTPUT_FORMAT: OnceLock<Option<OutputFormat>> = OnceLock::new();
+static COLOR_CHOICE: OnceLock<termcolor::ColorChoice> = OnceLock::new();
+static QUIET: OnceLock<bool> = OnceLock::new();
+static JSON_ERRORS: OnceLock<bool> = OnceLock::new();
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    JsonPretty,
+    /// One JSON value per line, lists are printed an item per line.
+    Jsonl,
+    Yaml,
+    Table,
+}
+
+/// Sets the output format, `None` when `--output` wasn't given.
+pub fn set_output_format(format: Option<OutputFormat>) {
+    let _ = OUTPUT_FORMAT.set(format);
+}
+
+pub fn output_format() -> OutputFormat {
+    OUTPUT_FORMAT.get().copied().flatten().unwrap_or_default()
+}
+
+/// Whether an output format was asked for rather than defaulted to.
+pub fn output_format_given() -> bool {
+    OUTPUT_FORMAT.get().copied().flatten().is_some()
+}
+
+pub fn set_json_errors(json_errors: bool) {
+    let _ = JSON_ERRORS.set(json_errors);
+}
+
+/// Whether errors should be printed to stderr as JSON rather than as text.
+pub fn json_errors() -> bool {
+    JSON_ERRORS.get().copied().unwrap_or_default()
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorMode {
+    /// Color when stderr is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+pub fn set_color_mode(mode: ColorMode) {
+    let enabled = match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            std::env::var_os("NO_COLOR")
+                .filter(|value| !value.is_empty())
+                .is_none()
+                && std::io::stderr().is_terminal()
+        }
+    };
+
+    // keep progress bars in line with our own messages
+    console::set_colors_enabled_stderr(enabled);
+
+    let _ = COLOR_CHOICE.set(if enabled {
+        termcolor::ColorChoice::Always
+    } else {
+        termcolor::ColorChoice::Never
+    });
+}
+
+fn color_choice() -> termcolor::ColorChoice {
+    COLOR_CHOICE
+        .get()
+        .copied()
+        .unwrap_or(termcolor::ColorChoice::Always)
+}
+
+pub fn set_quiet(quiet: bool) {
+    let _ = QUIET.set(quiet);
+}
+
+pub fn quiet() -> bool {
+    QUIET.get().copied().unwrap_or_default()
+}
+
+pub fn print_output<T: Serialize + ?Sized>(value: &T) -> Result<(), Error> {
+    match output_format() {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string(value).context(JsonSerializationSnafu)?
+            )
+        }
+        OutputFormat::JsonPretty => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(value).context(JsonSerializationSnafu)?
+            )
+        }
+        OutputFormat::Jsonl => match serde_json::to_value(value).context(JsonSerializationSnafu)? {
+            Value::Array(items) => {
+                for item in items {
+                    println!("{item}")
+                }
+            }
+            value => println!("{value}"),
+        },
+        OutputFormat::Yaml => {
+            print!(
+                "{}",
+                serde_yaml::to_string(value).context(YamlSerializationSnafu)?
+            )
+        }
+        OutputFormat::Table => {
+            let value = serde_json::to_value(value).context(JsonSerializationSnafu)?;
+            print!("{}", render_table(&value))
+        }
+    }
+
+    Ok(())
+}
+
+fn render_table(value: &Value) -> String {
+    let (headers, rows) = table_rows(value);
+
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(&headers).chain(rows.iter()) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+
+    out
+}
+
+// List responses wrap their items in an array field (e.g. `{"binaries": [...], "next_page": ...}`),
+// single resources wrap a single object (e.g. `{"binary": {...}}`).
+fn table_rows(value: &Value) -> (Vec<String>, Vec<Vec<String>>) {
+    let items = match value {
+        Value::Array(items) => Some(items),
+        Value::Object(map) => map.values().find_map(Value::as_array),
+        _ => None,
+    };
+
+    if let Some(items) = items {
+        let mut headers: Vec<String> = Vec::new();
+        for item in items {
+            if let Value::Object(map) = item {
+                for key in map.keys() {
+                    if !headers.contains(key) {
+                        headers.push(key.clone());
+                    }
+                }
+            }
+        }
+
+        if headers.is_empty() {
+            headers.push("value".to_string());
+            let rows = items.iter().map(|item| vec![table_cell(item)]).collect();
+            return (headers, rows);
+        }
+
+        let rows = items
+            .iter()
+            .map(|item| {
+                headers
+                    .iter()
+                    .map(|header| item.get(header).map(table_cell).unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+
+        return (headers, rows);
+    }
+
+    let object = match value {
+        Value::Object(map) if map.len() == 1 => match map.values().next() {
+            Some(Value::Object(inner)) => Some(inner),
+            _ => Some(map),
+        },
+        Value::Object(map) => Some(map),
+        _ => None,
+    };
+
+    let headers = vec!["field".to_string(), "value".to_string()];
+    let rows = match object {
+        Some(map) => map
+            .iter()
+            .map(|(key, value)| vec![key.clone(), table_cell(value)])
+            .collect(),
+        None => vec![vec![String::new(), table_cell(value)]],
+    };
+
+    (headers, rows)
+}
+
+fn table_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(string) => string.clone(),
+        other => other.to_string(),
+    }
+}
+
 pub struct StyledStr {
     messages: Vec<(Option<Style>, String)>,
 }
@@ -22,7 +247,7 @@ impl StyledStr {
     }
 
     pub fn print_err(&self) -> std::io::Result<()> {
-        let bufwtr = termcolor::BufferWriter::stderr(termcolor::ColorChoice::Always);
+        let bufwtr = termcolor::BufferWriter::stderr(color_choice());
         let mut buffer = bufwtr.buffer();
 
         for (style, message) in &self.messages {
@@ -83,6 +308,164 @@ pub fn maybe_json(data: Option<String>) -> Option<Map<String, Value>> {
     }
 }
 
+/// Write a private key to a new file, readable only by its owner on Unix.
+///
+/// Fails rather than overwrite an existing file.
+pub(crate) fn write_private_key(path: &Path, private_key_pem: &str) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)?.write_all(private_key_pem.as_bytes())
+}
+
+/// Expand a leading `~` and `$VAR`/`${VAR}` references in `path`.
+///
+/// `path` is returned unchanged when a referenced variable is not set, so the caller's
+/// usual missing-file error still names the path as written.
+pub fn expand_path(path: &str) -> String {
+    shellexpand::full(path)
+        .map(|expanded| expanded.into_owned())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// Normalize a `--base-url` value: default to `https://` when no scheme is given and strip
+/// any trailing slash.
+///
+/// Errors when the URL can't be parsed or has no host.
+pub fn normalize_base_url(base_url: &str) -> Result<String, Error> {
+    let invalid = |reason: String| Error::InvalidBaseUrl {
+        base_url: base_url.to_string(),
+        reason,
+    };
+
+    let with_scheme = if base_url.contains("://") {
+        base_url.to_string()
+    } else {
+        format!("https://{base_url}")
+    };
+
+    let url = reqwest::Url::parse(&with_scheme).map_err(|error| invalid(error.to_string()))?;
+
+    if url.host_str().filter(|host| !host.is_empty()).is_none() {
+        return Err(invalid("missing host".to_string()));
+    }
+
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+/// The broad cause of a failed API request.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ApiFailure {
+    /// The TLS handshake failed, usually because the CA bundle doesn't match the server.
+    Tls,
+    /// The server could not be reached.
+    Network,
+    /// The API key was rejected.
+    Unauthorized,
+    /// Any other failure.
+    Other,
+}
+
+impl ApiFailure {
+    /// Classify `error` by its source chain.
+    ///
+    /// Only failures raised while connecting are classified as TLS or network failures, so an
+    /// API response that merely mentions a certificate is not mistaken for a TLS failure.
+    pub fn of(error: &peridio_sdk::api::Error) -> Self {
+        let chain: Vec<_> = error_chain(error).collect();
+        let messages: Vec<_> = chain
+            .iter()
+            .map(|cause| cause.to_string().to_lowercase())
+            .collect();
+
+        let connect = chain
+            .iter()
+            .zip(&messages)
+            .position(|(cause, message)| is_connect_error(*cause, message));
+
+        if let Some(connect) = connect {
+            // the causes of the connection error tell a failed handshake apart from the rest
+            let tls = messages[connect..].iter().any(|message| {
+                ["certificate", "tls", "ssl", "unknownissuer", "handshake"]
+                    .iter()
+                    .any(|needle| message.contains(needle))
+            });
+
+            return if tls { Self::Tls } else { Self::Network };
+        }
+
+        let mentions = |needles: &[&str]| {
+            messages
+                .iter()
+                .any(|message| needles.iter().any(|needle| message.contains(needle)))
+        };
+
+        if mentions(&[
+            "error sending request",
+            "connection refused",
+            "dns error",
+            "timed out",
+        ]) {
+            Self::Network
+        } else if http_status(error) == Some(reqwest::StatusCode::UNAUTHORIZED)
+            || mentions(&["unauthorized"])
+        {
+            Self::Unauthorized
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// The HTTP status `error` was caused by, if any.
+///
+/// Prefers the status carried by a `reqwest::Error` in the source chain and otherwise falls back
+/// to a status code leading one of the chain's messages, e.g. `429 Too Many Requests`. Numbers
+/// elsewhere in a message, such as in PRNs, are never taken for a status.
+pub fn http_status(error: &peridio_sdk::api::Error) -> Option<reqwest::StatusCode> {
+    error_chain(error).find_map(|cause| {
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .and_then(reqwest::Error::status)
+            .or_else(|| leading_status(&cause.to_string()))
+    })
+}
+
+/// `error` followed by each of its sources in turn.
+fn error_chain<'a>(
+    error: &'a (dyn std::error::Error + 'static),
+) -> impl Iterator<Item = &'a (dyn std::error::Error + 'static)> {
+    std::iter::successors(Some(error), |cause| cause.source())
+}
+
+/// Whether `cause` is the failure to establish a connection, rather than a response from the API.
+fn is_connect_error(cause: &(dyn std::error::Error + 'static), message: &str) -> bool {
+    cause
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(reqwest::Error::is_connect)
+        || message.starts_with("error trying to connect")
+        || message.starts_with("client error (connect)")
+}
+
+fn leading_status(message: &str) -> Option<reqwest::StatusCode> {
+    let code = message
+        .trim_start()
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?;
+
+    if code.len() != 3 {
+        return None;
+    }
+
+    reqwest::StatusCode::from_u16(code.parse().ok()?).ok()
+}
+
 fn prn_error(cmd: &clap::Command, arg: Option<&clap::Arg>, error: &str) -> clap::Error {
     let mut err = clap::Error::new(ErrorKind::ValueValidation).with_cmd(cmd);
     if let Some(arg) = arg {
@@ -129,43 +512,106 @@ pub enum PRNType {
     UserToken,
 }
 
+impl PRNType {
+    /// Every PRN resource type, in declaration order.
+    pub const ALL: [PRNType; 27] = [
+        Self::APIKey,
+        Self::Artifact,
+        Self::ArtifactVersion,
+        Self::AuditLog,
+        Self::Binary,
+        Self::BinaryPart,
+        Self::BinarySignature,
+        Self::Bundle,
+        Self::BundleOverride,
+        Self::CACertificate,
+        Self::Cohort,
+        Self::Deployment,
+        Self::Device,
+        Self::DeviceCertificate,
+        Self::Event,
+        Self::Firmware,
+        Self::OrgUser,
+        Self::Organization,
+        Self::Product,
+        Self::Release,
+        Self::ReleaseClaim,
+        Self::SigningKey,
+        Self::Tunnel,
+        Self::User,
+        Self::WebConsoleShell,
+        Self::Webhook,
+        Self::UserToken,
+    ];
+
+    /// The resource type segment used for this type in a PRN.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::APIKey => "api_key",
+            Self::Artifact => "artifact",
+            Self::ArtifactVersion => "artifact_version",
+            Self::AuditLog => "audit_log",
+            Self::Binary => "binary",
+            Self::BinaryPart => "binary_part",
+            Self::BinarySignature => "binary_signature",
+            Self::Bundle => "bundle",
+            Self::BundleOverride => "bundle_override",
+            Self::CACertificate => "ca_certificate",
+            Self::Cohort => "cohort",
+            Self::Deployment => "deployment",
+            Self::Device => "device",
+            Self::DeviceCertificate => "device_certificate",
+            Self::Event => "event",
+            Self::Firmware => "firmware",
+            Self::OrgUser => "org_user",
+            Self::Organization => "organization",
+            Self::Product => "product",
+            Self::Release => "release",
+            Self::ReleaseClaim => "release_claim",
+            Self::SigningKey => "signing_key",
+            Self::Tunnel => "tunnel",
+            Self::User => "user",
+            Self::WebConsoleShell => "web_console_shell",
+            Self::Webhook => "webhook",
+            Self::UserToken => "user_token",
+        }
+    }
+}
+
 impl TryFrom<String> for PRNType {
     type Error = &'static str;
 
     fn try_from(value: String) -> Result<PRNType, Self::Error> {
-        match value.as_str() {
-            "api_key" => Ok(Self::APIKey),
-            "artifact" => Ok(Self::Artifact),
-            "artifact_version" => Ok(Self::ArtifactVersion),
-            "audit_log" => Ok(Self::AuditLog),
-            "binary" => Ok(Self::Binary),
-            "binary_part" => Ok(Self::BinaryPart),
-            "binary_signature" => Ok(Self::BinarySignature),
-            "bundle" => Ok(Self::Bundle),
-            "bundle_override" => Ok(Self::BundleOverride),
-            "ca_certificate" => Ok(Self::CACertificate),
-            "cohort" => Ok(Self::Cohort),
-            "deployment" => Ok(Self::Deployment),
-            "device" => Ok(Self::Device),
-            "device_certificate" => Ok(Self::DeviceCertificate),
-            "event" => Ok(Self::Event),
-            "firmware" => Ok(Self::Firmware),
-            "org_user" => Ok(Self::OrgUser),
-            "organization" => Ok(Self::Organization),
-            "product" => Ok(Self::Product),
-            "release" => Ok(Self::Release),
-            "release_claim" => Ok(Self::ReleaseClaim),
-            "signing_key" => Ok(Self::SigningKey),
-            "tunnel" => Ok(Self::Tunnel),
-            "user" => Ok(Self::User),
-            "web_console_shell" => Ok(Self::WebConsoleShell),
-            "webhook" => Ok(Self::Webhook),
-            "user_token" => Ok(Self::UserToken),
-            _ => Err("Invalid PRN type"),
-        }
+        // derived from `as_str` so the two mappings can't drift apart
+        Self::ALL
+            .into_iter()
+            .find(|prn_type| prn_type.as_str() == value)
+            .ok_or("Invalid PRN type")
     }
 }
 
+/// Why a value was rejected as a PRN.
+#[derive(Debug, PartialEq, Snafu)]
+pub enum PrnParseError {
+    #[snafu(display(
+        "Invalid PRN, expected 'prn:1:<organization id>' or 'prn:1:[<organization id>:]<type>:<id>'"
+    ))]
+    Malformed,
+
+    #[snafu(display("Invalid PRN type '{}'", prn_type))]
+    UnknownType { prn_type: String },
+
+    #[snafu(display(
+        "Invalid PRN type, expected a {} PRN but got a {} PRN",
+        expected.as_str(),
+        found.as_str()
+    ))]
+    UnexpectedType { expected: PRNType, found: PRNType },
+
+    #[snafu(display("Invalid PRN UUID '{}', expected a valid UUID", uuid))]
+    InvalidUuid { uuid: String },
+}
+
 #[derive(Clone, PartialEq)]
 pub struct PRNValueParser(PRNType);
 
@@ -173,6 +619,55 @@ impl PRNValueParser {
     pub fn new(prn_type: PRNType) -> Self {
         Self(prn_type)
     }
+
+    /// Check that `value` is a well-formed PRN of the type this parser expects.
+    pub fn validate(&self, value: &str) -> Result<(), PrnParseError> {
+        let segments: Vec<&str> = value.split(':').collect();
+
+        let (organization, prn_type, id) = match segments.as_slice() {
+            ["prn", "1", organization] => (Some(*organization), None, None),
+            // resources that aren't scoped to an organization, e.g. users
+            ["prn", "1", prn_type, id] => (None, Some(*prn_type), Some(*id)),
+            ["prn", "1", organization, prn_type, id] => {
+                (Some(*organization), Some(*prn_type), Some(*id))
+            }
+            _ => return Err(PrnParseError::Malformed),
+        };
+
+        if let Some(organization) = organization {
+            validate_uuid(organization)?;
+        }
+
+        let found = match prn_type {
+            Some(prn_type) => {
+                PRNType::try_from(prn_type.to_string()).map_err(|_| PrnParseError::UnknownType {
+                    prn_type: prn_type.to_string(),
+                })?
+            }
+            None => PRNType::Organization,
+        };
+
+        if found != self.0 {
+            return Err(PrnParseError::UnexpectedType {
+                expected: self.0.clone(),
+                found,
+            });
+        }
+
+        if let Some(id) = id {
+            validate_uuid(id)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_uuid(uuid: &str) -> Result<(), PrnParseError> {
+    Uuid::try_parse(uuid)
+        .map(|_| ())
+        .map_err(|_| PrnParseError::InvalidUuid {
+            uuid: uuid.to_string(),
+        })
 }
 
 impl clap::builder::TypedValueParser for PRNValueParser {
@@ -184,105 +679,228 @@ impl clap::builder::TypedValueParser for PRNValueParser {
         arg: Option<&clap::Arg>,
         value: &std::ffi::OsStr,
     ) -> Result<Self::Value, clap::Error> {
-        let value: String = value.to_str().unwrap().to_owned();
+        let value = value
+            .to_str()
+            .ok_or_else(|| prn_error(cmd, arg, &PrnParseError::Malformed.to_string()))?;
 
-        let mut split = value.split(':').fuse();
+        self.validate(value)
+            .map_err(|error| prn_error(cmd, arg, &error.to_string()))?;
 
-        let prn_length = split.clone().count();
+        Ok(value.to_owned())
+    }
+}
 
-        if !(3..=5).contains(&prn_length) {
-            return Err(prn_error(cmd, arg, "Invalid PRN"));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prn_type_round_trips_through_try_from() {
+        for prn_type in PRNType::ALL {
+            assert_eq!(
+                PRNType::try_from(prn_type.as_str().to_string()),
+                Ok(prn_type)
+            );
         }
+    }
 
-        if split.next().is_some_and(|x| x != "prn") {
-            return Err(prn_error(cmd, arg, "Invalid PRN"));
-        }
+    #[test]
+    fn prn_type_rejects_unknown_resource_type() {
+        assert!(PRNType::try_from("not_a_resource".to_string()).is_err());
+    }
 
-        if split.next().is_some_and(|x| x != "1") {
-            return Err(prn_error(cmd, arg, "Invalid PRN"));
-        }
+    const UUID: &str = "f1d1b1b6-8c6b-4d7c-9a8e-2a9c0f6b3c11";
 
-        match prn_length {
-            3 => {
-                // organization prn only
-                if self.0 != PRNType::Organization {
-                    return Err(prn_error(cmd, arg, "Invalid PRN type"));
-                }
-                // the uuid has to be valid
-                if Uuid::try_parse(split.next().unwrap()).is_err() {
-                    return Err(prn_error(
-                        cmd,
-                        arg,
-                        "Invalid PRN UUID, expected 'organization' UUID in PRN",
-                    ));
-                }
+    fn validate(prn_type: PRNType, value: &str) -> Result<(), PrnParseError> {
+        PRNValueParser::new(prn_type).validate(value)
+    }
 
-                0
-            }
-            4 => {
-                // user or user token
-                if self.0 != PRNType::User || self.0 != PRNType::UserToken {
-                    return Err(prn_error(cmd, arg, "Invalid PRN type"));
-                }
+    #[test]
+    fn prn_value_parser_accepts_organization_prn() {
+        let prn = format!("prn:1:{UUID}");
+
+        assert_eq!(validate(PRNType::Organization, &prn), Ok(()));
+        assert_eq!(
+            validate(PRNType::Binary, &prn),
+            Err(PrnParseError::UnexpectedType {
+                expected: PRNType::Binary,
+                found: PRNType::Organization,
+            })
+        );
+        assert_eq!(
+            validate(PRNType::Organization, "prn:1:not-a-uuid"),
+            Err(PrnParseError::InvalidUuid {
+                uuid: "not-a-uuid".to_string(),
+            })
+        );
+    }
 
-                let prn_type = PRNType::try_from(split.next().unwrap().to_string());
+    #[test]
+    fn prn_value_parser_accepts_unscoped_prn() {
+        assert_eq!(
+            validate(PRNType::User, &format!("prn:1:user:{UUID}")),
+            Ok(())
+        );
+        assert_eq!(
+            validate(PRNType::UserToken, &format!("prn:1:user_token:{UUID}")),
+            Ok(())
+        );
+        assert_eq!(
+            validate(PRNType::User, &format!("prn:1:user_token:{UUID}")),
+            Err(PrnParseError::UnexpectedType {
+                expected: PRNType::User,
+                found: PRNType::UserToken,
+            })
+        );
+        assert_eq!(
+            validate(PRNType::User, "prn:1:user:not-a-uuid"),
+            Err(PrnParseError::InvalidUuid {
+                uuid: "not-a-uuid".to_string(),
+            })
+        );
+    }
 
-                if prn_type.is_err() {
-                    return Err(prn_error(cmd, arg, "Invalid PRN type"));
-                }
+    #[test]
+    fn prn_value_parser_accepts_organization_scoped_prn() {
+        assert_eq!(
+            validate(PRNType::Binary, &format!("prn:1:{UUID}:binary:{UUID}")),
+            Ok(())
+        );
+        assert_eq!(
+            validate(PRNType::Binary, &format!("prn:1:{UUID}:signing_key:{UUID}")),
+            Err(PrnParseError::UnexpectedType {
+                expected: PRNType::Binary,
+                found: PRNType::SigningKey,
+            })
+        );
+        assert_eq!(
+            validate(PRNType::Binary, &format!("prn:1:{UUID}:gadget:{UUID}")),
+            Err(PrnParseError::UnknownType {
+                prn_type: "gadget".to_string(),
+            })
+        );
+        assert_eq!(
+            validate(PRNType::Binary, &format!("prn:1:{UUID}:binary:nope")),
+            Err(PrnParseError::InvalidUuid {
+                uuid: "nope".to_string(),
+            })
+        );
+        assert_eq!(
+            validate(PRNType::Binary, &format!("prn:1:nope:binary:{UUID}")),
+            Err(PrnParseError::InvalidUuid {
+                uuid: "nope".to_string(),
+            })
+        );
+    }
 
-                let prn_type = prn_type.unwrap();
+    #[test]
+    fn prn_value_parser_rejects_malformed_prn() {
+        for value in [
+            "prn:1".to_string(),
+            format!("prn:1:{UUID}:binary:{UUID}:x"),
+            format!("urn:1:{UUID}"),
+            format!("prn:2:{UUID}"),
+        ] {
+            assert_eq!(
+                validate(PRNType::Organization, &value),
+                Err(PrnParseError::Malformed)
+            );
+        }
+    }
 
-                if prn_type != PRNType::User || prn_type != PRNType::UserToken {
-                    return Err(prn_error(
-                        cmd,
-                        arg,
-                        "Invalid PRN type, expected 'user' or 'user_token' PRN",
-                    ));
-                }
+    #[test]
+    fn normalize_base_url_defaults_scheme_and_strips_trailing_slash() {
+        assert_eq!(
+            normalize_base_url("api.peridio.com/").unwrap(),
+            "https://api.peridio.com"
+        );
+        assert_eq!(
+            normalize_base_url("http://localhost:4000/").unwrap(),
+            "http://localhost:4000"
+        );
+        assert_eq!(
+            normalize_base_url("https://example.com/api/").unwrap(),
+            "https://example.com/api"
+        );
+    }
 
-                0
-            }
-            5 => {
-                // the org uuid has to be valid
-                if Uuid::try_parse(split.next().unwrap()).is_err() {
-                    return Err(prn_error(
-                        cmd,
-                        arg,
-                        "Invalid PRN UUID, expected valid UUID in PRN",
-                    ));
-                }
+    #[test]
+    fn normalize_base_url_rejects_missing_host() {
+        assert!(normalize_base_url("https://").is_err());
+        assert!(normalize_base_url("file:///tmp/api").is_err());
+    }
 
-                let prn_type = PRNType::try_from(split.next().unwrap().to_string());
+    #[test]
+    fn api_failure_classifies_error_messages() {
+        let failure = |error: &str| {
+            ApiFailure::of(&peridio_sdk::api::Error::Unknown {
+                error: error.to_string(),
+            })
+        };
 
-                if prn_type.is_err() {
-                    return Err(prn_error(cmd, arg, "Invalid PRN type"));
-                }
+        assert_eq!(
+            failure("error trying to connect: invalid peer certificate: UnknownIssuer"),
+            ApiFailure::Tls
+        );
+        assert_eq!(
+            failure("error trying to connect: tcp connect error: Connection refused"),
+            ApiFailure::Network
+        );
+        assert_eq!(failure("401 Unauthorized"), ApiFailure::Unauthorized);
+        assert_eq!(failure("422 Unprocessable Entity"), ApiFailure::Other);
+    }
 
-                let prn_type = prn_type.unwrap();
+    #[test]
+    fn api_failure_ignores_certificates_in_api_responses() {
+        let failure = |error: &str| {
+            ApiFailure::of(&peridio_sdk::api::Error::Unknown {
+                error: error.to_string(),
+            })
+        };
 
-                if self.0 != prn_type {
-                    return Err(prn_error(
-                        cmd,
-                        arg,
-                        format!("Invalid PRN type, expected '{:#?}' PRN", self.0).as_str(),
-                    ));
-                }
+        assert_eq!(
+            failure("404 Not Found: ca certificate not found"),
+            ApiFailure::Other
+        );
+        assert_eq!(
+            failure("422 Unprocessable Entity: device certificate already exists"),
+            ApiFailure::Other
+        );
+    }
 
-                // the uuid has to be valid
-                if Uuid::try_parse(split.next().unwrap()).is_err() {
-                    return Err(prn_error(
-                        cmd,
-                        arg,
-                        "Invalid PRN UUID, expected valid UUID in PRN",
-                    ));
-                }
+    #[test]
+    fn http_status_reads_only_a_leading_status() {
+        let status = |error: &str| {
+            http_status(&peridio_sdk::api::Error::Unknown {
+                error: error.to_string(),
+            })
+        };
 
-                0
-            }
-            _ => return Err(prn_error(cmd, arg, "Invalid PRN")),
+        assert_eq!(
+            status("429 Too Many Requests"),
+            Some(reqwest::StatusCode::TOO_MANY_REQUESTS)
+        );
+        assert_eq!(
+            status("422 Unprocessable Entity: prn:1:5f0c1a2e-4291-4d7c-9a8e-2a9c0f6b3c11"),
+            Some(reqwest::StatusCode::UNPROCESSABLE_ENTITY)
+        );
+        assert_eq!(
+            status("binary prn:1:5f0c1a2e-4291-4d7c-9a8e-2a9c0f6b3503 not found"),
+            None
+        );
+        assert_eq!(status("4291 parts"), None);
+    }
+
+    #[test]
+    fn prn_parse_error_names_both_types() {
+        let error = PrnParseError::UnexpectedType {
+            expected: PRNType::Binary,
+            found: PRNType::SigningKey,
         };
 
-        Ok(value)
+        assert_eq!(
+            error.to_string(),
+            "Invalid PRN type, expected a binary PRN but got a signing_key PRN"
+        );
     }
 }
@@ -0,0 +1,135 @@
+use std::future::Future;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use crate::utils::{http_status, ApiFailure};
+use crate::Error;
+
+/// The delay before the first retry, doubled on each following one.
+pub const DEFAULT_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// The longest any single retry will wait.
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Run `operation`, retrying it up to `max_retries` times for as long as `retryable` accepts
+/// the error it returned.
+///
+/// Retries use exponential backoff with full jitter: retry `n` sleeps a random duration in
+/// `[0, base_delay * 2^n]`, so concurrent callers that fail together don't retry in lockstep.
+pub async fn retry_with_backoff<T, E, F, Fut, P>(
+    mut operation: F,
+    max_retries: usize,
+    base_delay: Duration,
+    mut retryable: P,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    P: FnMut(&E) -> bool,
+{
+    let mut retries = 0;
+
+    loop {
+        match operation().await {
+            Err(error) if retries < max_retries && retryable(&error) => {
+                tokio::time::sleep(jittered_delay(base_delay, retries)).await;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// A random delay in `[0, base_delay * 2^retries]`, capped at [`MAX_DELAY`].
+fn jittered_delay(base_delay: Duration, retries: usize) -> Duration {
+    let ceiling = u32::try_from(retries)
+        .ok()
+        .and_then(|retries| 2u32.checked_pow(retries))
+        .and_then(|factor| base_delay.checked_mul(factor))
+        .map_or(MAX_DELAY, |delay| delay.min(MAX_DELAY));
+
+    ceiling.mul_f64(rand::random::<f64>())
+}
+
+/// Whether `error` is the API rejecting a request because of rate limiting.
+pub fn is_rate_limited(error: &Error) -> bool {
+    match error {
+        Error::Api { source } => is_api_rate_limited(source),
+        _ => false,
+    }
+}
+
+/// Like [`is_rate_limited`], for SDK errors that haven't been wrapped in an [`Error`] yet.
+pub fn is_api_rate_limited(error: &peridio_sdk::api::Error) -> bool {
+    http_status(error) == Some(StatusCode::TOO_MANY_REQUESTS)
+        || error.to_string().contains("Too Many Requests")
+}
+
+/// Whether `error` is likely to go away on its own: rate limiting, a server error or the API
+/// being unreachable.
+pub fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::Api { source } => {
+            is_rate_limited(error)
+                || ApiFailure::of(source) == ApiFailure::Network
+                || matches!(
+                    http_status(source),
+                    Some(
+                        StatusCode::INTERNAL_SERVER_ERROR
+                            | StatusCode::BAD_GATEWAY
+                            | StatusCode::SERVICE_UNAVAILABLE
+                            | StatusCode::GATEWAY_TIMEOUT
+                    )
+                )
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_error(error: &str) -> Error {
+        Error::Api {
+            source: peridio_sdk::api::Error::Unknown {
+                error: error.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn is_rate_limited_matches_status_429() {
+        assert!(is_rate_limited(&api_error("429 Too Many Requests")));
+        assert!(!is_rate_limited(&api_error(
+            "422 Unprocessable Entity: prn:1:5f0c1a2e-4291-4d7c-9a8e-2a9c0f6b3c11"
+        )));
+        assert!(!is_rate_limited(&Error::EmptyResponse {
+            context: "429".to_string(),
+        }));
+    }
+
+    #[test]
+    fn is_transient_matches_server_errors_and_network_failures() {
+        assert!(is_transient(&api_error("429 Too Many Requests")));
+        assert!(is_transient(&api_error("503 Service Unavailable")));
+        assert!(is_transient(&api_error(
+            "error trying to connect: tcp connect error: Connection refused"
+        )));
+        assert!(!is_transient(&api_error("404 Not Found")));
+        assert!(!is_transient(&api_error(
+            "422 Unprocessable Entity: prn:1:5f0c1a2e-5030-4d7c-9a8e-2a9c0f6b3502"
+        )));
+    }
+
+    #[test]
+    fn jittered_delay_is_capped() {
+        for retries in [0, 1, 5, 10, 64, usize::MAX] {
+            assert!(jittered_delay(DEFAULT_BASE_DELAY, retries) <= MAX_DELAY);
+        }
+
+        assert!(jittered_delay(DEFAULT_BASE_DELAY, 0) <= DEFAULT_BASE_DELAY);
+        assert!(jittered_delay(Duration::from_millis(100), 2) <= Duration::from_millis(400));
+    }
+}
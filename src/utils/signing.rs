@@ -0,0 +1,299 @@
+use aws_lc_rs::rand::SystemRandom;
+use aws_lc_rs::signature::{
+    EcdsaKeyPair, Ed25519KeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING,
+};
+use base64::engine::general_purpose;
+use base64::Engine;
+use ed25519_dalek::pkcs8::DecodePrivateKey;
+use ed25519_dalek::Signer;
+use x509_parser::der_parser::asn1_rs::{Any, Sequence};
+use x509_parser::prelude::FromDer;
+use x509_parser::x509::{AlgorithmIdentifier, SubjectPublicKeyInfo};
+
+use crate::Error;
+
+const OID_ED25519: &str = "1.3.101.112";
+const OID_EC_PUBLIC_KEY: &str = "1.2.840.10045.2.1";
+const OID_EC_P256: &str = "1.2.840.10045.3.1.7";
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum SigningKeyAlgorithm {
+    #[default]
+    Ed25519,
+    EcdsaP256,
+}
+
+/// Generate a new key pair, returning the PKCS#8 private key as a PEM and the public key
+/// value as expected by the signing keys API.
+pub fn generate_key_pair(algorithm: SigningKeyAlgorithm) -> Result<(String, String), Error> {
+    let rng = SystemRandom::new();
+
+    let (pkcs8, public_key) = match algorithm {
+        SigningKeyAlgorithm::Ed25519 => {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng)
+                .map_err(|_| signing_key_error("failed to generate an Ed25519 key pair"))?;
+            let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref())
+                .map_err(|e| signing_key_error(&e.to_string()))?;
+            let public_key = key_pair.public_key().as_ref().to_vec();
+
+            (pkcs8.as_ref().to_vec(), public_key)
+        }
+        SigningKeyAlgorithm::EcdsaP256 => {
+            let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+                .map_err(|_| signing_key_error("failed to generate an ECDSA P-256 key pair"))?;
+            let key_pair =
+                EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref())
+                    .map_err(|e| signing_key_error(&e.to_string()))?;
+            let public_key = key_pair.public_key().as_ref().to_vec();
+
+            (pkcs8.as_ref().to_vec(), public_key)
+        }
+    };
+
+    Ok((
+        encode_pem("PRIVATE KEY", &pkcs8),
+        general_purpose::STANDARD.encode(public_key),
+    ))
+}
+
+/// Read a public key PEM and encode its raw key bytes as expected by the signing keys API.
+///
+/// Ed25519 keys are encoded as their 32 byte public key, ECDSA P-256 keys as their
+/// uncompressed SEC1 point.
+pub fn public_key_value_from_pem(pem: &str) -> Result<String, Error> {
+    let der = decode_pem(pem)?;
+
+    let (_, spki) = SubjectPublicKeyInfo::from_der(&der)
+        .map_err(|_| signing_key_error("invalid public key PEM"))?;
+
+    key_algorithm(&spki.algorithm)?;
+
+    Ok(general_purpose::STANDARD.encode(&spki.subject_public_key.data))
+}
+
+/// Derive the public key value, as expected by the signing keys API, from a PKCS#8 private
+/// key PEM.
+pub fn public_key_value_from_private_pem(private_key_pem: &str) -> Result<String, Error> {
+    let der = decode_pem(private_key_pem)?;
+
+    let public_key = match private_key_algorithm(&der)? {
+        SigningKeyAlgorithm::Ed25519 => ed25519_dalek::SigningKey::from_pkcs8_der(&der)
+            .map_err(|e| signing_key_error(&e.to_string()))?
+            .verifying_key()
+            .to_bytes()
+            .to_vec(),
+        SigningKeyAlgorithm::EcdsaP256 => {
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &der)
+                .map_err(|e| signing_key_error(&e.to_string()))?
+                .public_key()
+                .as_ref()
+                .to_vec()
+        }
+    };
+
+    Ok(general_purpose::STANDARD.encode(public_key))
+}
+
+/// Sign `message` with the PKCS#8 private key PEM, returning the upper case hex encoded
+/// signature.
+///
+/// The signing algorithm is picked from the algorithm identifier of the private key.
+pub fn sign(private_key_pem: &str, message: &[u8]) -> Result<String, Error> {
+    let der = decode_pem(private_key_pem)?;
+
+    match private_key_algorithm(&der)? {
+        SigningKeyAlgorithm::Ed25519 => {
+            let signing_key = ed25519_dalek::SigningKey::from_pkcs8_der(&der)
+                .map_err(|e| signing_key_error(&e.to_string()))?;
+            let signature = signing_key.sign(message);
+
+            Ok(format!("{signature:X}"))
+        }
+        SigningKeyAlgorithm::EcdsaP256 => {
+            let key_pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &der)
+                .map_err(|e| signing_key_error(&e.to_string()))?;
+            let signature = key_pair
+                .sign(&SystemRandom::new(), message)
+                .map_err(|_| signing_key_error("failed to create an ECDSA P-256 signature"))?;
+
+            Ok(encode_hex_upper(signature.as_ref()))
+        }
+    }
+}
+
+/// Read the algorithm identifier of a DER encoded PKCS#8 private key.
+///
+/// ```text
+/// PrivateKeyInfo ::= SEQUENCE {
+///     version             INTEGER,
+///     privateKeyAlgorithm AlgorithmIdentifier,
+///     privateKey          OCTET STRING,
+///     ... }
+/// ```
+fn private_key_algorithm(der: &[u8]) -> Result<SigningKeyAlgorithm, Error> {
+    let invalid = |_| signing_key_error("invalid PKCS#8 private key");
+
+    let (_, private_key_info) = Sequence::from_der(der).map_err(invalid)?;
+    let (rest, _version) = Any::from_der(&private_key_info.content).map_err(invalid)?;
+    let (_, algorithm) = AlgorithmIdentifier::from_der(rest)
+        .map_err(|_| signing_key_error("invalid PKCS#8 private key"))?;
+
+    key_algorithm(&algorithm)
+}
+
+fn key_algorithm(algorithm: &AlgorithmIdentifier) -> Result<SigningKeyAlgorithm, Error> {
+    let oid = algorithm.algorithm.to_id_string();
+
+    match oid.as_str() {
+        OID_ED25519 => Ok(SigningKeyAlgorithm::Ed25519),
+        OID_EC_PUBLIC_KEY => {
+            let curve = algorithm
+                .parameters
+                .as_ref()
+                .and_then(|parameters| parameters.as_oid().ok())
+                .map(|curve| curve.to_id_string())
+                .unwrap_or_default();
+
+            if curve == OID_EC_P256 {
+                Ok(SigningKeyAlgorithm::EcdsaP256)
+            } else {
+                Err(Error::UnsupportedKeyAlgorithm {
+                    oid: format!("{oid} (curve {curve})"),
+                })
+            }
+        }
+        _ => Err(Error::UnsupportedKeyAlgorithm { oid }),
+    }
+}
+
+fn decode_pem(pem: &str) -> Result<Vec<u8>, Error> {
+    let (_, pem) = x509_parser::pem::parse_x509_pem(pem.as_bytes())
+        .map_err(|_| signing_key_error("invalid PEM"))?;
+
+    Ok(pem.contents)
+}
+
+fn encode_pem(label: &str, der: &[u8]) -> String {
+    let encoded = general_purpose::STANDARD.encode(der);
+
+    let mut pem = format!("-----BEGIN {label}-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {label}-----\n"));
+
+    pem
+}
+
+fn encode_hex_upper(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02X}")).collect()
+}
+
+fn signing_key_error(reason: &str) -> Error {
+    Error::SigningKey {
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_lc_rs::signature::{
+        UnparsedPublicKey, VerificationAlgorithm, ECDSA_P256_SHA256_ASN1, ECDSA_P256_SHA256_FIXED,
+        ECDSA_P384_SHA384_FIXED_SIGNING, ED25519,
+    };
+
+    use super::*;
+
+    const MESSAGE: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    fn decode_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn verify(algorithm: &'static dyn VerificationAlgorithm, public_key: &str, signature: &str) {
+        let public_key = general_purpose::STANDARD.decode(public_key).unwrap();
+
+        UnparsedPublicKey::new(algorithm, public_key)
+            .verify(MESSAGE, &decode_hex(signature))
+            .unwrap();
+    }
+
+    #[test]
+    fn generated_key_pairs_derive_their_public_key() {
+        for algorithm in [SigningKeyAlgorithm::Ed25519, SigningKeyAlgorithm::EcdsaP256] {
+            let (private_key_pem, public_key) = generate_key_pair(algorithm).unwrap();
+
+            assert_eq!(
+                public_key_value_from_private_pem(&private_key_pem).unwrap(),
+                public_key
+            );
+        }
+    }
+
+    #[test]
+    fn ed25519_signatures_verify_with_the_public_key() {
+        let (private_key_pem, public_key) =
+            generate_key_pair(SigningKeyAlgorithm::Ed25519).unwrap();
+        let signature = sign(&private_key_pem, MESSAGE).unwrap();
+
+        assert_eq!(signature, signature.to_uppercase());
+        verify(&ED25519, &public_key, &signature);
+    }
+
+    #[test]
+    fn ecdsa_p256_signatures_are_raw_r_and_s() {
+        let (private_key_pem, public_key) =
+            generate_key_pair(SigningKeyAlgorithm::EcdsaP256).unwrap();
+        let signature = sign(&private_key_pem, MESSAGE).unwrap();
+
+        // the API expects the fixed size r || s encoding, not an ASN.1 DER signature
+        assert_eq!(decode_hex(&signature).len(), 64);
+        verify(&ECDSA_P256_SHA256_FIXED, &public_key, &signature);
+
+        let public_key = general_purpose::STANDARD.decode(&public_key).unwrap();
+        assert!(UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, public_key)
+            .verify(MESSAGE, &decode_hex(&signature))
+            .is_err());
+    }
+
+    fn unsupported_oid(private_key_pem: &str) -> String {
+        match sign(private_key_pem, MESSAGE) {
+            Err(Error::UnsupportedKeyAlgorithm { oid }) => oid,
+            result => panic!("expected an unsupported key algorithm, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn sign_rejects_ec_keys_on_other_curves() {
+        let pkcs8 =
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P384_SHA384_FIXED_SIGNING, &SystemRandom::new())
+                .unwrap();
+        let private_key_pem = encode_pem("PRIVATE KEY", pkcs8.as_ref());
+
+        assert_eq!(
+            unsupported_oid(&private_key_pem),
+            "1.2.840.10045.2.1 (curve 1.3.132.0.34)"
+        );
+    }
+
+    #[test]
+    fn sign_rejects_unsupported_key_algorithms() {
+        // an Ed448 PKCS#8 key, algorithm 1.3.101.113, with a zeroed private key
+        let mut der = vec![
+            0x30, 0x47, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2B, 0x65, 0x71, 0x04, 0x3B,
+            0x04, 0x39,
+        ];
+        der.resize(der.len() + 57, 0);
+        let private_key_pem = encode_pem("PRIVATE KEY", &der);
+
+        assert_eq!(unsupported_oid(&private_key_pem), "1.3.101.113");
+        assert_eq!(
+            sign(&private_key_pem, MESSAGE).unwrap_err().to_string(),
+            "unsupported key algorithm: 1.3.101.113"
+        );
+    }
+}
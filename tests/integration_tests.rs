@@ -50,7 +50,16 @@ fn without_command_usage_is_shown() {
             predicates::str::contains("-d, --config-directory <CONFIG_DIRECTORY>").and(
                 predicates::str::contains("[env: PERIDIO_CONFIG_DIRECTORY=]"),
             ),
-        );
+        )
+        .stderr(
+            predicates::str::contains("-O, --output <OUTPUT>")
+                .and(predicates::str::contains("[env: PERIDIO_OUTPUT=]")),
+        )
+        .stderr(
+            predicates::str::contains("--color <COLOR>")
+                .and(predicates::str::contains("[env: PERIDIO_COLOR=]")),
+        )
+        .stderr(predicates::str::contains("-q, --quiet"));
 }
 
 #[test]